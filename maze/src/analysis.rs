@@ -0,0 +1,102 @@
+use crate::grid::Grid;
+use crate::solve::dijkstra::longest_path;
+use crate::topology::Topology;
+
+/// Numbers describing what a maze is like to walk through, for comparing generators.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stats {
+    /// Fraction of cells with only one passage.
+    pub dead_ends: f64,
+    /// Cells on the longest path through the maze as a fraction of all cells.
+    pub solution_length: f64,
+    /// Fraction of passages going east to west. Half means there's no bias either way.
+    pub horizontal_bias: f64,
+    /// Average number of passages in a corridor before you reach a junction or dead end.
+    /// High means long winding rivers, low means lots of decisions.
+    pub river: f64,
+    /// Fraction of cells with three or more passages, where you have to choose which way to go.
+    pub branching: f64,
+}
+
+impl Stats {
+    pub fn of(grid: &Grid) -> Stats {
+        let size = grid.size() as f64;
+        let mut horizontal = 0;
+        let mut vertical = 0;
+        let mut junctions = 0;
+        // Corridors end at any cell that isn't just a bend or straight line, so each one is counted twice.
+        let mut corridor_ends = 0;
+        for pos in grid.positions() {
+            let links = grid.links(pos).len();
+            if links != 2 {
+                corridor_ends += links;
+            }
+            if links >= 3 {
+                junctions += 1;
+            }
+
+            // Count each passage once, from the cell on its west or north side.
+            if grid.is_linked(pos, grid.east(pos)) {
+                horizontal += 1;
+            }
+            if grid.is_linked(pos, grid.south(pos)) {
+                vertical += 1;
+            }
+        }
+
+        let passages = horizontal + vertical;
+        let corridors = corridor_ends / 2;
+        Stats {
+            dead_ends: grid.dead_ends().len() as f64 / size,
+            solution_length: longest_path(grid).len() as f64 / size,
+            horizontal_bias: if passages == 0 { 0.5 } else { horizontal as f64 / passages as f64 },
+            river: if corridors == 0 { passages as f64 } else { passages as f64 / corridors as f64 },
+            branching: junctions as f64 / size,
+        }
+    }
+
+    /// The average of each number.
+    pub fn mean(all: &[Stats]) -> Stats {
+        let count = all.len().max(1) as f64;
+        let average = |get: fn(&Stats) -> f64| all.iter().map(get).sum::<f64>() / count;
+        Stats {
+            dead_ends: average(|stats| stats.dead_ends),
+            solution_length: average(|stats| stats.solution_length),
+            horizontal_bias: average(|stats| stats.horizontal_bias),
+            river: average(|stats| stats.river),
+            branching: average(|stats| stats.branching),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Pos;
+    use crate::mask::Mask;
+
+    #[test]
+    fn corridor() {
+        let mut grid = Grid::new(4, 1);
+        for col in 0..3 {
+            grid.link(Pos::of(0, col), Pos::of(0, col + 1));
+        }
+        let stats = Stats::of(&grid);
+        assert_eq!(stats.dead_ends * 4.0, 2.0);
+        assert_eq!(stats.solution_length, 1.0);
+        assert_eq!(stats.horizontal_bias, 1.0);
+        // One river all the way along.
+        assert_eq!(stats.river, 3.0);
+        assert_eq!(stats.branching, 0.0);
+    }
+
+    #[test]
+    fn one_cell() {
+        let mask = Mask::from_ascii("XXX\nX X\nXXX").unwrap();
+        let stats = Stats::of(&Grid::masked(mask));
+        assert_eq!(stats.dead_ends, 0.0);
+        assert_eq!(stats.solution_length, 1.0);
+        assert_eq!(stats.horizontal_bias, 0.5);
+        assert_eq!(stats.river, 0.0);
+    }
+}
@@ -0,0 +1,252 @@
+use std::fmt::Write;
+use crate::grid::{Grid, Pos};
+use crate::solve::dijkstra::Distances;
+
+/// How to draw a maze as an image.
+pub struct ExportOptions<'a> {
+    /// Pixels across each cell.
+    pub cell_size: i32,
+    /// Shade each cell by how far it is from the root, darker is further.
+    pub distances: Option<&'a Distances<'a>>,
+    /// Drawn as a line through the middle of each cell in order.
+    pub path: &'a [Pos],
+}
+
+impl<'a> ExportOptions<'a> {
+    pub fn new(cell_size: i32) -> ExportOptions<'a> {
+        ExportOptions {
+            cell_size,
+            distances: None,
+            path: &[],
+        }
+    }
+}
+
+type Colour = [u8; 3];
+
+const BACKGROUND: Colour = [255, 255, 255];
+const WALL: Colour = [0, 0, 0];
+const PATH: Colour = [220, 40, 40];
+
+/// Pixels around the edge so the outer walls aren't cut off.
+const MARGIN: i32 = 2;
+
+/// Everything to draw, in pixels, shared by each output format.
+struct Picture {
+    width: i32,
+    height: i32,
+    /// Top left corner, size and colour of each cell.
+    cells: Vec<(i32, i32, i32, Colour)>,
+    /// Each wall from (x1, y1) to (x2, y2). Always horizontal or vertical.
+    walls: Vec<(i32, i32, i32, i32)>,
+    /// Centres of each cell on the path. Split where it goes around a wrapping edge.
+    paths: Vec<Vec<(i32, i32)>>,
+}
+
+impl Picture {
+    fn of(grid: &Grid, options: &ExportOptions) -> Picture {
+        let size = options.cell_size;
+        let corner = |pos: Pos| (MARGIN + (pos.col * size), MARGIN + (pos.row * size));
+        let centre = |pos: Pos| {
+            let (x, y) = corner(pos);
+            (x + (size / 2), y + (size / 2))
+        };
+
+        let mut cells = vec![];
+        let mut walls = vec![];
+        let max_distance = options.distances.map_or(0, |distances| distances.max().1);
+        for pos in grid.positions() {
+            let (x1, y1) = corner(pos);
+            let (x2, y2) = (x1 + size, y1 + size);
+
+            let distance = options.distances.and_then(|distances| distances.get(pos));
+            if let Some(distance) = distance {
+                cells.push((x1, y1, size, distance_colour(distance, max_distance)));
+            }
+
+            // Same as the world, each wall is drawn by the cell on its south or east side unless there's nothing there.
+            let north = grid.north(pos);
+            let west = grid.west(pos);
+            if !grid.has(north) || (pos.row == 0 && !grid.is_linked(pos, north)) {
+                walls.push((x1, y1, x2, y1));
+            }
+            if !grid.has(west) || (pos.col == 0 && !grid.is_linked(pos, west)) {
+                walls.push((x1, y1, x1, y2));
+            }
+            if !grid.is_linked(pos, grid.east(pos)) {
+                walls.push((x2, y1, x2, y2));
+            }
+            if !grid.is_linked(pos, grid.south(pos)) {
+                walls.push((x1, y2, x2, y2));
+            }
+        }
+
+        let mut paths = vec![];
+        let mut current: Vec<(i32, i32)> = vec![];
+        for (i, pos) in options.path.iter().enumerate() {
+            let next_to_last = i > 0 && {
+                let last = options.path[i - 1];
+                (last.row - pos.row).abs() + (last.col - pos.col).abs() == 1
+            };
+            if !next_to_last && !current.is_empty() {
+                paths.push(std::mem::take(&mut current));
+            }
+            current.push(centre(*pos));
+        }
+        if !current.is_empty() {
+            paths.push(current);
+        }
+
+        Picture {
+            width: (grid.cols * size) + (2 * MARGIN) + 1,
+            height: (grid.rows * size) + (2 * MARGIN) + 1,
+            cells,
+            walls,
+            paths,
+        }
+    }
+}
+
+/// Green that gets darker the further the cell is from the root.
+fn distance_colour(distance: i32, max_distance: i32) -> Colour {
+    let intensity = if max_distance == 0 { 1.0 } else { (max_distance - distance) as f64 / max_distance as f64 };
+    let dark = (255.0 * intensity) as u8;
+    let bright = (128.0 + (127.0 * intensity)) as u8;
+    [dark, bright, dark]
+}
+
+/// A vector image that can be scaled up for printing without getting blurry.
+pub fn to_svg(grid: &Grid, options: &ExportOptions) -> String {
+    let picture = Picture::of(grid, options);
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        picture.width, picture.height
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="{}"/>"#, svg_colour(BACKGROUND));
+
+    for (x, y, size, colour) in &picture.cells {
+        let _ = writeln!(svg, r#"<rect x="{}" y="{}" width="{2}" height="{2}" fill="{3}"/>"#, x, y, size, svg_colour(*colour));
+    }
+
+    let _ = writeln!(svg, r#"<g stroke="{}" stroke-width="2" stroke-linecap="square">"#, svg_colour(WALL));
+    for (x1, y1, x2, y2) in &picture.walls {
+        let _ = writeln!(svg, r#"<line x1="{}" y1="{}" x2="{}" y2="{}"/>"#, x1, y1, x2, y2);
+    }
+    svg.push_str("</g>\n");
+
+    for path in &picture.paths {
+        let points: Vec<String> = path.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+        let _ = writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linejoin="round" stroke-linecap="round"/>"#,
+            points.join(" "), svg_colour(PATH), path_width(options)
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn svg_colour(colour: Colour) -> String {
+    format!("rgb({},{},{})", colour[0], colour[1], colour[2])
+}
+
+fn path_width(options: &ExportOptions) -> i32 {
+    (options.cell_size / 8).max(1)
+}
+
+/// A binary (P6) portable pixmap, which almost anything can convert to other image formats.
+pub fn to_ppm(grid: &Grid, options: &ExportOptions) -> Vec<u8> {
+    let picture = Picture::of(grid, options);
+    let mut image = Image::new(picture.width, picture.height);
+
+    for (x, y, size, colour) in &picture.cells {
+        image.fill(*x, *y, *x + size, *y + size, *colour);
+    }
+
+    // Walls are 2 pixels thick to match the SVG.
+    for (x1, y1, x2, y2) in &picture.walls {
+        image.fill(*x1 - 1, *y1 - 1, *x2 + 1, *y2 + 1, WALL);
+    }
+
+    let half_width = path_width(options) / 2;
+    for path in &picture.paths {
+        for pair in path.windows(2) {
+            let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+            image.fill(x1.min(x2) - half_width, y1.min(y2) - half_width, x1.max(x2) + half_width + 1, y1.max(y2) + half_width + 1, PATH);
+        }
+        if let [(x, y)] = path[..] {
+            image.fill(x - half_width, y - half_width, x + half_width + 1, y + half_width + 1, PATH);
+        }
+    }
+
+    let mut ppm = format!("P6\n{} {}\n255\n", image.width, image.height).into_bytes();
+    for pixel in image.pixels {
+        ppm.extend_from_slice(&pixel);
+    }
+    ppm
+}
+
+struct Image {
+    width: i32,
+    height: i32,
+    pixels: Vec<Colour>,
+}
+
+impl Image {
+    fn new(width: i32, height: i32) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![BACKGROUND; (width * height) as usize],
+        }
+    }
+
+    /// Colours the rectangle from (x1, y1) up to but not including (x2, y2), clipped to the image.
+    fn fill(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, colour: Colour) {
+        for y in y1.max(0)..y2.min(self.height) {
+            for x in x1.max(0)..x2.min(self.width) {
+                self.pixels[((y * self.width) + x) as usize] = colour;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2x2 grid open everywhere except between the two bottom cells.
+    fn grid() -> Grid {
+        let mut grid = Grid::new(2, 2);
+        grid.link(Pos::of(0, 0), Pos::of(0, 1));
+        grid.link(Pos::of(0, 0), Pos::of(1, 0));
+        grid.link(Pos::of(0, 1), Pos::of(1, 1));
+        grid
+    }
+
+    #[test]
+    fn svg_walls() {
+        let svg = to_svg(&grid(), &ExportOptions::new(10));
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="25" height="25""#));
+        // Four sides of the outside each split in two and the one between the bottom cells.
+        assert_eq!(svg.matches("<line").count(), 9);
+        assert!(svg.contains(r#"<line x1="12" y1="12" x2="12" y2="22"/>"#));
+        assert!(!svg.contains(r#"<line x1="12" y1="2" x2="12" y2="12"/>"#));
+    }
+
+    #[test]
+    fn ppm_pixels() {
+        let ppm = to_ppm(&grid(), &ExportOptions::new(10));
+        let header = b"P6\n25 25\n255\n";
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(ppm.len(), header.len() + (25 * 25 * 3));
+
+        let pixel = |x: usize, y: usize| &ppm[header.len() + ((y * 25) + x) * 3..][..3];
+        assert_eq!(pixel(12, 17), WALL);
+        assert_eq!(pixel(12, 7), BACKGROUND);
+        assert_eq!(pixel(2, 7), WALL);
+    }
+}
@@ -1,2 +1,334 @@
 pub mod binary_tree;
+pub mod sidewinder;
+pub mod aldous_broder;
+pub mod wilsons;
+pub mod hunt_and_kill;
+pub mod recursive_backtracker;
+pub mod kruskals;
+pub mod simplified_prims;
+pub mod true_prims;
+pub mod growing_tree;
+pub mod ellers;
+pub mod recursive_division;
 
+use crate::grid::{Grid, Pos};
+use crate::topology::Topology;
+use crate::Random;
+
+/// One thing a generator did to the grid, so it can be animated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Change {
+    Link(Pos, Pos),
+    /// Only recursive division takes passages away.
+    Unlink(Pos, Pos),
+}
+
+impl Change {
+    /// Does the same thing to another grid, to replay a generator onto a fresh one.
+    pub fn apply<G: Topology + ?Sized>(self, grid: &mut G) {
+        match self {
+            Change::Link(a, b) => grid.link(a, b),
+            Change::Unlink(a, b) => grid.unlink(a, b),
+        }
+    }
+}
+
+/// A generator running one change at a time. Each change has already been made to the grid by the time it comes out.
+pub type Changes<'a> = Box<dyn Iterator<Item = Change> + 'a>;
+
+/// A maze generation algorithm with a name, so which one to use can be chosen at runtime.
+#[derive(Clone, Copy)]
+pub struct MazeGenerator {
+    pub name: &'static str,
+    pub on: fn(&mut Grid, &mut Random),
+    pub steps: for<'a> fn(&'a mut Grid, &'a mut Random) -> Changes<'a>,
+    /// The same algorithm for any shape of grid. None if it only makes sense for rectangles.
+    pub on_any: Option<fn(&mut (dyn Topology + 'static), &mut Random)>,
+    pub steps_any: Option<for<'a> fn(&'a mut (dyn Topology + 'static), &'a mut Random) -> Changes<'a>>,
+}
+
+pub const GENERATORS: [MazeGenerator; 12] = [
+    MazeGenerator {
+        name: "binary_tree",
+        on: binary_tree::on,
+        steps: binary_tree::steps,
+        on_any: None,
+        steps_any: None,
+    },
+    MazeGenerator {
+        name: "sidewinder",
+        on: sidewinder::on,
+        steps: sidewinder::steps,
+        on_any: None,
+        steps_any: None,
+    },
+    MazeGenerator {
+        name: "aldous_broder",
+        on: aldous_broder::on,
+        steps: aldous_broder::steps,
+        on_any: Some(aldous_broder::on),
+        steps_any: Some(aldous_broder::steps),
+    },
+    MazeGenerator {
+        name: "wilsons",
+        on: wilsons::on,
+        steps: wilsons::steps,
+        on_any: Some(wilsons::on),
+        steps_any: Some(wilsons::steps),
+    },
+    MazeGenerator {
+        name: "hunt_and_kill",
+        on: hunt_and_kill::on,
+        steps: hunt_and_kill::steps,
+        on_any: Some(hunt_and_kill::on),
+        steps_any: Some(hunt_and_kill::steps),
+    },
+    MazeGenerator {
+        name: "recursive_backtracker",
+        on: recursive_backtracker::on,
+        steps: recursive_backtracker::steps,
+        on_any: Some(recursive_backtracker::on),
+        steps_any: Some(recursive_backtracker::steps),
+    },
+    MazeGenerator {
+        name: "kruskals",
+        on: kruskals::on,
+        steps: kruskals::steps,
+        on_any: Some(kruskals::on),
+        steps_any: Some(kruskals::steps),
+    },
+    MazeGenerator {
+        name: "simplified_prims",
+        on: simplified_prims::on,
+        steps: simplified_prims::steps,
+        on_any: Some(simplified_prims::on),
+        steps_any: Some(simplified_prims::steps),
+    },
+    MazeGenerator {
+        name: "true_prims",
+        on: true_prims::on,
+        steps: true_prims::steps,
+        on_any: Some(true_prims::on),
+        steps_any: Some(true_prims::steps),
+    },
+    MazeGenerator {
+        name: "growing_tree",
+        on: growing_tree::on,
+        steps: growing_tree::steps,
+        on_any: Some(growing_tree::on),
+        steps_any: Some(growing_tree::steps),
+    },
+    MazeGenerator {
+        name: "ellers",
+        on: ellers::on,
+        steps: ellers::steps,
+        on_any: None,
+        steps_any: None,
+    },
+    MazeGenerator {
+        name: "recursive_division",
+        on: recursive_division::on,
+        steps: recursive_division::steps,
+        on_any: None,
+        steps_any: None,
+    },
+];
+
+impl MazeGenerator {
+    pub fn by_name(name: &str) -> Option<MazeGenerator> {
+        GENERATORS.iter().find(|generator| generator.name == name).copied()
+    }
+
+    /// The generator after this one in GENERATORS, wrapping around to the start.
+    pub fn next(&self) -> MazeGenerator {
+        let i = GENERATORS.iter().position(|generator| generator.name == self.name).unwrap_or(0);
+        GENERATORS[(i + 1) % GENERATORS.len()]
+    }
+
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        GENERATORS.iter().map(|generator| generator.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Pos;
+    use crate::{HexGrid, LayeredGrid, Mask, PolarGrid, TriangleGrid, WeaveGrid};
+
+    const BINARY_TREE: &str = "\
++---+---+---+---+---+
++                   |
+|---+---+   +   +   +
++           |   |   |
+|   +   +   +---+   +
++   |   |   |       |
+|---+---+   +   +   +
++           |   |   |
+|---+---+---+---+---+
+";
+
+    const RECURSIVE_BACKTRACKER: &str = "\
++---+---+---+---+---+
++           |       |
+|   +---+---+   +   +
++   |           |   |
+|   +   +---+---+   +
++       |   |       |
+|   +---+   +   +---+
++           |       |
+|---+---+---+---+---+
+";
+
+    /// The same seed should always make the same maze.
+    fn assert_matches(name: &str, fixture: &str) {
+        let mut grid = Grid::new(5, 4);
+        (MazeGenerator::by_name(name).unwrap().on)(&mut grid, &mut Random::new(1));
+        let expected = Grid::from_ascii(fixture).unwrap();
+        for row in 0..grid.rows {
+            for col in 0..grid.cols {
+                let pos = Pos::of(row, col);
+                let mut links = grid.links(pos).to_vec();
+                let mut expected_links = expected.links(pos).to_vec();
+                links.sort_by_key(|pos| (pos.row, pos.col));
+                expected_links.sort_by_key(|pos| (pos.row, pos.col));
+                assert_eq!(links, expected_links, "{} at {:?}", name, pos);
+            }
+        }
+    }
+
+    #[test]
+    fn fixtures() {
+        assert_matches("binary_tree", BINARY_TREE);
+        assert_matches("recursive_backtracker", RECURSIVE_BACKTRACKER);
+    }
+
+    fn assert_perfect(grid: &dyn Topology, name: &str, shape: &str) {
+        assert_eq!(grid.validate(), Ok(()), "{} on {}", name, shape);
+        assert_eq!(grid.connected_components().len(), 1, "{} on {}", name, shape);
+        assert!(grid.is_perfect(), "{} on {}", name, shape);
+    }
+
+    #[test]
+    fn perfect() {
+        let mask = Mask::from_ascii("\
+X.....X
+.......
+...X...
+.......
+X.....X
+").unwrap();
+        for generator in GENERATORS {
+            for seed in 0..3 {
+                let rng = &mut Random::new(seed);
+                let mut grid = Grid::new(9, 7);
+                (generator.on)(&mut grid, rng);
+                assert_perfect(&grid, generator.name, "grid");
+
+                // Rectangle only generators can't go around the edges so they still make valid mazes, just not perfect ones.
+                let mut wrapping = Grid::new(6, 5).wrapping(true, true);
+                (generator.on)(&mut wrapping, rng);
+                assert_eq!(wrapping.validate(), Ok(()), "{} on wrapping grid", generator.name);
+
+                let mut braided = Grid::new(9, 7);
+                (generator.on)(&mut braided, rng);
+                braided.braid(1.0, rng);
+                assert_eq!(braided.validate(), Ok(()), "{} braided", generator.name);
+                assert_eq!(braided.connected_components().len(), 1, "{} braided", generator.name);
+                assert!(!braided.is_perfect(), "{} braided", generator.name);
+
+                let Some(on_any) = generator.on_any else {
+                    continue;
+                };
+                let shapes: [(&str, Box<dyn Topology>); 7] = [
+                    ("masked grid", Box::new(Grid::masked(mask.clone()))),
+                    ("wrapping grid", Box::new(Grid::new(6, 5).wrapping(true, true))),
+                    ("polar grid", Box::new(PolarGrid::new(5))),
+                    ("hex grid", Box::new(HexGrid::new(5, 6))),
+                    ("triangle grid", Box::new(TriangleGrid::new(5, 7))),
+                    ("layered grid", Box::new(LayeredGrid::new(3, 4, 4))),
+                    ("weave grid", Box::new(WeaveGrid::new(7, 7))),
+                ];
+                for (shape, mut grid) in shapes {
+                    on_any(grid.as_mut(), rng);
+                    assert_perfect(grid.as_ref(), generator.name, shape);
+                }
+            }
+        }
+    }
+
+    /// A mask split into separate areas, or with a cell on its own, still finishes without leaving any area half done.
+    #[test]
+    fn split_mask() {
+        let masks = [
+            Mask::from_ascii("..X..\n..X..\nXXXX.\n.X...\n").unwrap(),
+            Mask::from_ascii(".").unwrap(),
+        ];
+        for generator in GENERATORS {
+            let Some(on_any) = generator.on_any else {
+                continue;
+            };
+            for mask in &masks {
+                for seed in 0..5 {
+                    let mut grid = Grid::masked(mask.clone());
+                    on_any(&mut grid, &mut Random::new(seed));
+                    assert_eq!(grid.validate(), Ok(()), "{}", generator.name);
+                    for area in grid.connected_components().into_iter().filter(|area| area.len() > 1) {
+                        assert_eq!(area.len(), grid.reachable(area[0]).len(), "{}", generator.name);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Running a generator one change at a time makes the same maze, and replaying the changes on a fresh grid makes it again.
+    #[test]
+    fn steps() {
+        for generator in GENERATORS {
+            let mut expected = Grid::new(9, 7);
+            (generator.on)(&mut expected, &mut Random::new(5));
+            let mut grid = Grid::new(9, 7);
+            let changes: Vec<Change> = (generator.steps)(&mut grid, &mut Random::new(5)).collect();
+            assert_eq!(grid.to_string(), expected.to_string(), "{}", generator.name);
+
+            let mut replay = Grid::new(9, 7);
+            changes.iter().for_each(|change| change.apply(&mut replay));
+            assert_eq!(replay.to_string(), expected.to_string(), "{} replayed", generator.name);
+
+            let Some(steps_any) = generator.steps_any else {
+                continue;
+            };
+            let mut grid = PolarGrid::new(5);
+            let changes: Vec<Change> = steps_any(&mut grid, &mut Random::new(5)).collect();
+            assert_eq!(changes.len() + 1, grid.size(), "{} on polar grid", generator.name);
+            let mut replay = PolarGrid::new(5);
+            changes.iter().for_each(|change| change.apply(&mut replay));
+            for pos in grid.positions() {
+                assert_eq!(replay.links(pos).to_vec(), grid.links(pos).to_vec(), "{} replayed on polar grid", generator.name);
+            }
+        }
+    }
+
+    #[test]
+    fn stream() {
+        let mut stream = ellers::Stream::new(8, Random::new(3));
+        let mut rows: Vec<ellers::Row> = stream.by_ref().take(15).collect();
+        rows.push(stream.last_row());
+        assert_eq!(rows.iter().map(|row| row.index).collect::<Vec<i32>>(), (0..16).collect::<Vec<i32>>());
+        assert_eq!(ellers::Stream::new(8, Random::new(3)).nth(4).as_ref(), rows.get(4));
+
+        let mut grid = Grid::new(8, 16);
+        for row in &rows {
+            for col in 0..8 {
+                let pos = Pos::of(row.index, col);
+                if row.east[col as usize] {
+                    grid.link(pos, grid.east(pos));
+                }
+                if row.south[col as usize] {
+                    grid.link(pos, grid.south(pos));
+                }
+            }
+        }
+        assert_perfect(&grid, "ellers", "stream");
+    }
+}
@@ -0,0 +1,49 @@
+use crate::gen::{Change, Changes};
+use crate::grid::Pos;
+use crate::topology::Topology;
+use crate::Random;
+
+/// Randomly walk around the grid, linking to each cell the first time it's visited.
+/// Unbiased but slow to finish since it has to stumble onto the last few cells by chance.
+/// If a mask splits the grid into separate areas, only the one it starts in is filled.
+pub fn on<G: Topology + ?Sized>(grid: &mut G, rng: &mut Random) {
+    AldousBroder::new(grid, rng).for_each(drop);
+}
+
+pub fn steps<'a, G: Topology + ?Sized>(grid: &'a mut G, rng: &'a mut Random) -> Changes<'a> {
+    Box::new(AldousBroder::new(grid, rng))
+}
+
+struct AldousBroder<'a, G: ?Sized> {
+    grid: &'a mut G,
+    rng: &'a mut Random,
+    pos: Pos,
+    unvisited: usize,
+}
+
+impl<'a, G: Topology + ?Sized> AldousBroder<'a, G> {
+    fn new(grid: &'a mut G, rng: &'a mut Random) -> AldousBroder<'a, G> {
+        let pos = grid.random_pos(rng);
+        let unvisited = grid.reachable(pos).len() - 1;
+        AldousBroder { grid, rng, pos, unvisited }
+    }
+}
+
+impl<G: Topology + ?Sized> Iterator for AldousBroder<'_, G> {
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        while self.unvisited > 0 {
+            let neighbours = self.grid.neighbours(self.pos);
+            let next: Pos = neighbours[self.rng.rand_below(neighbours.len())];
+            let from = self.pos;
+            self.pos = next;
+            if self.grid.links(next).is_empty() {
+                self.grid.link(from, next);
+                self.unvisited -= 1;
+                return Some(Change::Link(from, next));
+            }
+        }
+        None
+    }
+}
@@ -1,32 +1,64 @@
+use crate::gen::{Change, Changes};
 use crate::grid::{Grid, Pos};
-use crate::rand_below;
+use crate::Random;
 
 /// For each cell, randomly choose either north or east to connect.
 /// Be careful not to go out of bounds on the north and east edges.
-pub fn on(grid: &mut Grid) {
-    let mut near: Vec<Pos> = Vec::with_capacity(2);
-    for row in 0..grid.rows {
-        for col in 0..grid.cols {
-            let pos = Pos::of(row, col);
-
-            let check = grid.north(pos);
-            if grid.has(check) {
-                near.push(check);
+/// Only makes a perfect maze if the grid isn't masked or wrapping.
+pub fn on(grid: &mut Grid, rng: &mut Random) {
+    BinaryTree::new(grid, rng).for_each(drop);
+}
+
+pub fn steps<'a>(grid: &'a mut Grid, rng: &'a mut Random) -> Changes<'a> {
+    Box::new(BinaryTree::new(grid, rng))
+}
+
+struct BinaryTree<'a> {
+    grid: &'a mut Grid,
+    rng: &'a mut Random,
+    /// The next cell to look at, going along each row in turn.
+    row: i32,
+    col: i32,
+}
+
+impl<'a> BinaryTree<'a> {
+    fn new(grid: &'a mut Grid, rng: &'a mut Random) -> BinaryTree<'a> {
+        BinaryTree { grid, rng, row: 0, col: 0 }
+    }
+}
+
+impl Iterator for BinaryTree<'_> {
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        while self.row < self.grid.rows {
+            let pos = Pos::of(self.row, self.col);
+            self.col += 1;
+            if self.col == self.grid.cols {
+                self.col = 0;
+                self.row += 1;
+            }
+            if !self.grid.has(pos) {
+                continue;
             }
 
-            let check = grid.east(pos);
-            if grid.has(check) {
-                near.push(check);
+            let mut near = [pos; 2];
+            let mut count = 0;
+            for check in [self.grid.north(pos), self.grid.east(pos)] {
+                if self.grid.has(check) {
+                    near[count] = check;
+                    count += 1;
+                }
             }
 
-            if near.is_empty() {
+            if count == 0 {
                 continue;
             }
 
-            let other = near[rand_below(near.len())];
-            grid.mut_cell(pos).links.push(other);
-            grid.mut_cell(other).links.push(pos);
-            near.clear();
+            let other = near[self.rng.rand_below(count)];
+            self.grid.link(pos, other);
+            return Some(Change::Link(pos, other));
         }
+        None
     }
-}
\ No newline at end of file
+}
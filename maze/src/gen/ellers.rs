@@ -0,0 +1,186 @@
+use std::collections::VecDeque;
+use crate::gen::{Change, Changes};
+use crate::grid::{Grid, Pos};
+use crate::Random;
+
+/// Works one row at a time, only remembering which set each cell in the current row belongs to.
+/// Randomly links neighbouring cells in different sets, then every set sends at least one passage south.
+/// The last row links every remaining set so the whole maze is connected.
+/// Only makes a perfect maze if the grid isn't masked. Never links across the edges of a wrapping grid.
+pub fn on(grid: &mut Grid, rng: &mut Random) {
+    Ellers::new(grid, rng).for_each(drop);
+}
+
+pub fn steps<'a>(grid: &'a mut Grid, rng: &'a mut Random) -> Changes<'a> {
+    Box::new(Ellers::new(grid, rng))
+}
+
+struct Ellers<'a> {
+    grid: &'a mut Grid,
+    rng: &'a mut Random,
+    sets: RowSets,
+    /// The next row to carve.
+    row: i32,
+    /// Passages already decided for the last row, waiting to be linked.
+    carving: VecDeque<(Pos, Pos)>,
+}
+
+impl<'a> Ellers<'a> {
+    fn new(grid: &'a mut Grid, rng: &'a mut Random) -> Ellers<'a> {
+        let sets = RowSets::new(grid.cols as usize);
+        Ellers { grid, rng, sets, row: 0, carving: VecDeque::new() }
+    }
+}
+
+impl Iterator for Ellers<'_> {
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        while self.carving.is_empty() && self.row < self.grid.rows {
+            let (grid, row) = (&self.grid, self.row);
+            let last_row = row == grid.rows - 1;
+            let passages = self.sets.carve_row(
+                self.rng,
+                last_row,
+                |col| grid.has(Pos::of(row, col as i32)),
+                |col| grid.has(Pos::of(row + 1, col as i32)),
+            );
+            for passage in passages {
+                let pos = Pos::of(row, passage.col as i32);
+                let other = if passage.south { grid.south(pos) } else { grid.east(pos) };
+                self.carving.push_back((pos, other));
+            }
+            self.row += 1;
+        }
+
+        let (a, b) = self.carving.pop_front()?;
+        self.grid.link(a, b);
+        Some(Change::Link(a, b))
+    }
+}
+
+/// Eller's algorithm making rows forever, for a maze that never ends.
+/// It only remembers which set each cell in the current row is in, so it takes the same memory however many rows it makes.
+pub struct Stream {
+    rng: Random,
+    sets: RowSets,
+    row: i32,
+}
+
+/// The passages out of one row of a Stream.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+    /// How many rows came before this one.
+    pub index: i32,
+    /// Whether each cell is linked to the one to its east.
+    pub east: Vec<bool>,
+    /// Whether each cell is linked to the one below it in the next row.
+    pub south: Vec<bool>,
+}
+
+impl Stream {
+    pub fn new(cols: i32, rng: Random) -> Stream {
+        Stream { rng, sets: RowSets::new(cols as usize), row: 0 }
+    }
+
+    /// A row that joins everything left so the maze made so far is perfect. The stream shouldn't be used after this.
+    pub fn last_row(&mut self) -> Row {
+        self.make_row(true)
+    }
+
+    fn make_row(&mut self, last_row: bool) -> Row {
+        let cols = self.sets.sets.len();
+        let mut row = Row { index: self.row, east: vec![false; cols], south: vec![false; cols] };
+        for passage in self.sets.carve_row(&mut self.rng, last_row, |_| true, |_| true) {
+            if passage.south {
+                row.south[passage.col] = true;
+            } else {
+                row.east[passage.col] = true;
+            }
+        }
+        self.row += 1;
+        row
+    }
+}
+
+impl Iterator for Stream {
+    type Item = Row;
+
+    /// Never runs out.
+    fn next(&mut self) -> Option<Row> {
+        Some(self.make_row(false))
+    }
+}
+
+/// A passage east or south from a cell in the row being carved.
+struct Passage {
+    col: usize,
+    south: bool,
+}
+
+/// Which set each cell in the current row belongs to, which is all Eller's algorithm needs to remember.
+struct RowSets {
+    sets: Vec<Option<usize>>,
+    next_set: usize,
+}
+
+impl RowSets {
+    fn new(cols: usize) -> RowSets {
+        RowSets { sets: vec![None; cols], next_set: 0 }
+    }
+
+    /// Decides all the passages east and south from one row and moves on to the next.
+    /// on and on_below say which cols have a cell in this row and the next.
+    fn carve_row(&mut self, rng: &mut Random, last_row: bool, on: impl Fn(usize) -> bool, on_below: impl Fn(usize) -> bool) -> Vec<Passage> {
+        let mut passages = vec![];
+        let sets = &mut self.sets;
+
+        // Cells that didn't get a passage from the north start in a new set.
+        for (col, set) in sets.iter_mut().enumerate() {
+            if !on(col) {
+                *set = None;
+            } else if set.is_none() {
+                *set = Some(self.next_set);
+                self.next_set += 1;
+            }
+        }
+
+        for col in 0..sets.len().saturating_sub(1) {
+            let (set, east_set) = (sets[col], sets[col + 1]);
+            let both_on = set.is_some() && east_set.is_some();
+            if both_on && set != east_set && (last_row || rng.rand_below(2) == 0) {
+                passages.push(Passage { col, south: false });
+                for check in sets.iter_mut() {
+                    if *check == east_set {
+                        *check = set;
+                    }
+                }
+            }
+        }
+
+        if last_row {
+            return passages;
+        }
+
+        let mut next_sets = vec![None; sets.len()];
+        let mut members: Vec<usize> = Vec::new();
+        for col in 0..sets.len() {
+            let set = sets[col];
+            if set.is_none() || (0..col).any(|earlier| sets[earlier] == set) {
+                continue;
+            }
+
+            members.clear();
+            members.extend((col..sets.len()).filter(|check| sets[*check] == set && on_below(*check)));
+            rng.shuffle(&mut members);
+            for (i, &member) in members.iter().enumerate() {
+                if i == 0 || rng.rand_below(3) == 0 {
+                    passages.push(Passage { col: member, south: true });
+                    next_sets[member] = set;
+                }
+            }
+        }
+        *sets = next_sets;
+        passages
+    }
+}
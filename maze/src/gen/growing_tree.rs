@@ -0,0 +1,72 @@
+use crate::gen::{Change, Changes};
+use crate::grid::Pos;
+use crate::topology::Topology;
+use crate::Random;
+
+/// Half the time grow from the newest cell and half the time from a random one,
+/// so it looks like a cross between the recursive backtracker and simplified Prim's.
+pub fn on<G: Topology + ?Sized>(grid: &mut G, rng: &mut Random) {
+    with(grid, rng, newest_or_random);
+}
+
+pub fn steps<'a, G: Topology + ?Sized>(grid: &'a mut G, rng: &'a mut Random) -> Changes<'a> {
+    Box::new(GrowingTree::new(grid, rng, newest_or_random))
+}
+
+fn newest_or_random(active: &[Pos], rng: &mut Random) -> usize {
+    if rng.rand_below(2) == 0 {
+        active.len() - 1
+    } else {
+        rng.rand_below(active.len())
+    }
+}
+
+/// Grow the maze from a list of active cells like Prim's, but the choice of which active cell
+/// to grow from is up to the caller. Always choosing the newest is the recursive backtracker,
+/// always choosing randomly is simplified Prim's.
+pub fn with<G: Topology + ?Sized>(grid: &mut G, rng: &mut Random, choose: fn(&[Pos], &mut Random) -> usize) {
+    GrowingTree::new(grid, rng, choose).for_each(drop);
+}
+
+struct GrowingTree<'a, G: ?Sized> {
+    grid: &'a mut G,
+    rng: &'a mut Random,
+    choose: fn(&[Pos], &mut Random) -> usize,
+    visited: Vec<bool>,
+    active: Vec<Pos>,
+}
+
+impl<'a, G: Topology + ?Sized> GrowingTree<'a, G> {
+    fn new(grid: &'a mut G, rng: &'a mut Random, choose: fn(&[Pos], &mut Random) -> usize) -> GrowingTree<'a, G> {
+        let start = grid.random_pos(rng);
+        let mut visited = vec![false; grid.capacity()];
+        visited[grid.index(start)] = true;
+        GrowingTree { grid, rng, choose, visited, active: vec![start] }
+    }
+}
+
+impl<G: Topology + ?Sized> Iterator for GrowingTree<'_, G> {
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        while !self.active.is_empty() {
+            let i = (self.choose)(&self.active, self.rng);
+            let pos = self.active[i];
+            let unvisited: Vec<Pos> = self.grid.neighbours(pos)
+                .into_iter()
+                .filter(|check| !self.visited[self.grid.index(*check)])
+                .collect();
+
+            if unvisited.is_empty() {
+                self.active.remove(i);
+            } else {
+                let next = unvisited[self.rng.rand_below(unvisited.len())];
+                self.grid.link(pos, next);
+                self.visited[self.grid.index(next)] = true;
+                self.active.push(next);
+                return Some(Change::Link(pos, next));
+            }
+        }
+        None
+    }
+}
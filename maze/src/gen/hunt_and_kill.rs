@@ -0,0 +1,72 @@
+use crate::gen::{Change, Changes};
+use crate::grid::Pos;
+use crate::topology::Topology;
+use crate::Random;
+
+/// Randomly walk to unvisited cells until you get stuck.
+/// Then scan the grid for the first unvisited cell next to a visited one, link them and keep walking from there.
+/// Makes long twisty passages with few dead ends.
+pub fn on<G: Topology + ?Sized>(grid: &mut G, rng: &mut Random) {
+    HuntAndKill::new(grid, rng).for_each(drop);
+}
+
+pub fn steps<'a, G: Topology + ?Sized>(grid: &'a mut G, rng: &'a mut Random) -> Changes<'a> {
+    Box::new(HuntAndKill::new(grid, rng))
+}
+
+struct HuntAndKill<'a, G: ?Sized> {
+    grid: &'a mut G,
+    rng: &'a mut Random,
+    current: Option<Pos>,
+}
+
+impl<'a, G: Topology + ?Sized> HuntAndKill<'a, G> {
+    fn new(grid: &'a mut G, rng: &'a mut Random) -> HuntAndKill<'a, G> {
+        let current = Some(grid.random_pos(rng));
+        HuntAndKill { grid, rng, current }
+    }
+
+    /// Finds the first unvisited cell next to a visited one and links them.
+    fn hunt(&mut self) -> Option<(Pos, Pos)> {
+        for pos in self.grid.positions() {
+            if !self.grid.links(pos).is_empty() {
+                continue;
+            }
+
+            let visited: Vec<Pos> = self.grid.neighbours(pos)
+                .into_iter()
+                .filter(|check| !self.grid.links(*check).is_empty())
+                .collect();
+            if !visited.is_empty() {
+                return Some((pos, visited[self.rng.rand_below(visited.len())]));
+            }
+        }
+
+        None
+    }
+}
+
+impl<G: Topology + ?Sized> Iterator for HuntAndKill<'_, G> {
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        let pos = self.current?;
+        let unvisited: Vec<Pos> = self.grid.neighbours(pos)
+            .into_iter()
+            .filter(|check| self.grid.links(*check).is_empty())
+            .collect();
+
+        let (from, to) = if !unvisited.is_empty() {
+            let next = unvisited[self.rng.rand_below(unvisited.len())];
+            self.current = Some(next);
+            (pos, next)
+        } else {
+            let (found, visited) = self.hunt()?;
+            self.current = Some(found);
+            (found, visited)
+        };
+
+        self.grid.link(from, to);
+        Some(Change::Link(from, to))
+    }
+}
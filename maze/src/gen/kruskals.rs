@@ -0,0 +1,94 @@
+use crate::gen::{Change, Changes};
+use crate::grid::Pos;
+use crate::topology::Topology;
+use crate::Random;
+
+/// Every cell starts in its own set. Go through every possible passage in a random order
+/// and link the two cells if they're in different sets, merging the sets.
+pub fn on<G: Topology + ?Sized>(grid: &mut G, rng: &mut Random) {
+    Kruskals::new(grid, rng).for_each(drop);
+}
+
+pub fn steps<'a, G: Topology + ?Sized>(grid: &'a mut G, rng: &'a mut Random) -> Changes<'a> {
+    Box::new(Kruskals::new(grid, rng))
+}
+
+struct Kruskals<'a, G: ?Sized> {
+    grid: &'a mut G,
+    passages: std::vec::IntoIter<(Pos, Pos)>,
+    sets: DisjointSets,
+}
+
+impl<'a, G: Topology + ?Sized> Kruskals<'a, G> {
+    fn new(grid: &'a mut G, rng: &mut Random) -> Kruskals<'a, G> {
+        let mut passages: Vec<(Pos, Pos)> = vec![];
+        for pos in grid.positions() {
+            for check in grid.neighbours(pos) {
+                // Only add each pair once.
+                if grid.index(check) > grid.index(pos) {
+                    passages.push((pos, check));
+                }
+            }
+        }
+        rng.shuffle(&mut passages);
+
+        let sets = DisjointSets::new(grid.capacity());
+        Kruskals { grid, passages: passages.into_iter(), sets }
+    }
+}
+
+impl<G: Topology + ?Sized> Iterator for Kruskals<'_, G> {
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        for (a, b) in self.passages.by_ref() {
+            if self.sets.merge(self.grid.index(a), self.grid.index(b)) {
+                self.grid.link(a, b);
+                return Some(Change::Link(a, b));
+            }
+        }
+        None
+    }
+}
+
+/// Union-find over cell indexes.
+struct DisjointSets {
+    parents: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(count: usize) -> DisjointSets {
+        DisjointSets {
+            parents: (0..count).collect()
+        }
+    }
+
+    fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // Point everything on the way directly at the root so the next lookup is faster.
+        let mut current = index;
+        while self.parents[current] != root {
+            let next = self.parents[current];
+            self.parents[current] = root;
+            current = next;
+        }
+
+        root
+    }
+
+    /// Returns false if they were already in the same set.
+    fn merge(&mut self, a: usize, b: usize) -> bool {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a == b {
+            return false;
+        }
+
+        self.parents[b] = a;
+        true
+    }
+}
@@ -0,0 +1,51 @@
+use crate::gen::{Change, Changes};
+use crate::grid::Pos;
+use crate::topology::Topology;
+use crate::Random;
+
+/// Like hunt and kill, but when you get stuck, backtrack along your own path
+/// to the most recent cell that still has unvisited neighbours instead of scanning the grid.
+/// Uses an explicit stack so big grids can't overflow the real one.
+pub fn on<G: Topology + ?Sized>(grid: &mut G, rng: &mut Random) {
+    RecursiveBacktracker::new(grid, rng).for_each(drop);
+}
+
+pub fn steps<'a, G: Topology + ?Sized>(grid: &'a mut G, rng: &'a mut Random) -> Changes<'a> {
+    Box::new(RecursiveBacktracker::new(grid, rng))
+}
+
+struct RecursiveBacktracker<'a, G: ?Sized> {
+    grid: &'a mut G,
+    rng: &'a mut Random,
+    stack: Vec<Pos>,
+}
+
+impl<'a, G: Topology + ?Sized> RecursiveBacktracker<'a, G> {
+    fn new(grid: &'a mut G, rng: &'a mut Random) -> RecursiveBacktracker<'a, G> {
+        let stack = vec![grid.random_pos(rng)];
+        RecursiveBacktracker { grid, rng, stack }
+    }
+}
+
+impl<G: Topology + ?Sized> Iterator for RecursiveBacktracker<'_, G> {
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        while let Some(&pos) = self.stack.last() {
+            let unvisited: Vec<Pos> = self.grid.neighbours(pos)
+                .into_iter()
+                .filter(|check| self.grid.links(*check).is_empty())
+                .collect();
+
+            if unvisited.is_empty() {
+                self.stack.pop();
+            } else {
+                let next = unvisited[self.rng.rand_below(unvisited.len())];
+                self.grid.link(pos, next);
+                self.stack.push(next);
+                return Some(Change::Link(pos, next));
+            }
+        }
+        None
+    }
+}
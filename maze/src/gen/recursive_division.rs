@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+use crate::gen::{Change, Changes};
+use crate::grid::{Grid, Pos};
+use crate::Random;
+
+/// Starts with every cell linked, then splits the grid in two with a wall that has a single gap in it.
+/// Repeat on each half until the areas are only one cell wide.
+/// Makes long straight walls, so it looks more like a building than the others.
+/// Only makes a perfect maze if the grid isn't masked or wrapping.
+pub fn on(grid: &mut Grid, rng: &mut Random) {
+    RecursiveDivision::new(grid, rng).for_each(drop);
+}
+
+pub fn steps<'a>(grid: &'a mut Grid, rng: &'a mut Random) -> Changes<'a> {
+    Box::new(RecursiveDivision::new(grid, rng))
+}
+
+/// An area still to be divided.
+#[derive(Clone, Copy)]
+struct Area {
+    row: i32,
+    col: i32,
+    height: i32,
+    width: i32,
+}
+
+struct RecursiveDivision<'a> {
+    grid: &'a mut Grid,
+    rng: &'a mut Random,
+    /// Areas waiting to be divided, with the next one on top so they go in the same order as recursing would.
+    areas: Vec<Area>,
+    changes: VecDeque<Change>,
+}
+
+impl<'a> RecursiveDivision<'a> {
+    fn new(grid: &'a mut Grid, rng: &'a mut Random) -> RecursiveDivision<'a> {
+        let mut changes = VecDeque::new();
+        for pos in grid.positions().collect::<Vec<Pos>>() {
+            for check in [grid.south(pos), grid.east(pos)] {
+                if grid.has(check) {
+                    changes.push_back(Change::Link(pos, check));
+                }
+            }
+        }
+
+        let areas = vec![Area { row: 0, col: 0, height: grid.rows, width: grid.cols }];
+        RecursiveDivision { grid, rng, areas, changes }
+    }
+
+    fn divide(&mut self, area: Area) {
+        let Area { row, col, height, width } = area;
+        if height <= 1 || width <= 1 {
+            return;
+        }
+
+        if height > width || (height == width && self.rng.rand_below(2) == 0) {
+            self.divide_horizontally(row, col, height, width);
+        } else {
+            self.divide_vertically(row, col, height, width);
+        }
+    }
+
+    fn divide_horizontally(&mut self, row: i32, col: i32, height: i32, width: i32) {
+        let divide_south_of = self.rng.rand_below((height - 1) as usize) as i32;
+        let passage_at = self.rng.rand_below(width as usize) as i32;
+
+        for x in 0..width {
+            if x == passage_at {
+                continue;
+            }
+            let pos = Pos::of(row + divide_south_of, col + x);
+            self.changes.push_back(Change::Unlink(pos, self.grid.south(pos)));
+        }
+
+        self.areas.push(Area { row: row + divide_south_of + 1, col, height: height - divide_south_of - 1, width });
+        self.areas.push(Area { row, col, height: divide_south_of + 1, width });
+    }
+
+    fn divide_vertically(&mut self, row: i32, col: i32, height: i32, width: i32) {
+        let divide_east_of = self.rng.rand_below((width - 1) as usize) as i32;
+        let passage_at = self.rng.rand_below(height as usize) as i32;
+
+        for y in 0..height {
+            if y == passage_at {
+                continue;
+            }
+            let pos = Pos::of(row + y, col + divide_east_of);
+            self.changes.push_back(Change::Unlink(pos, self.grid.east(pos)));
+        }
+
+        self.areas.push(Area { row, col: col + divide_east_of + 1, height, width: width - divide_east_of - 1 });
+        self.areas.push(Area { row, col, height, width: divide_east_of + 1 });
+    }
+}
+
+impl Iterator for RecursiveDivision<'_> {
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        while self.changes.is_empty() {
+            let area = self.areas.pop()?;
+            self.divide(area);
+        }
+
+        let change = self.changes.pop_front()?;
+        change.apply(&mut *self.grid);
+        Some(change)
+    }
+}
@@ -0,0 +1,68 @@
+use crate::gen::{Change, Changes};
+use crate::grid::{Grid, Pos};
+use crate::Random;
+
+/// Go through each row building up a run of cells linked to the east.
+/// Randomly close the run by linking one of its cells to the north.
+/// The north row can't close runs so it ends up as one long corridor.
+/// Only makes a perfect maze if the grid isn't masked or wrapping.
+pub fn on(grid: &mut Grid, rng: &mut Random) {
+    Sidewinder::new(grid, rng).for_each(drop);
+}
+
+pub fn steps<'a>(grid: &'a mut Grid, rng: &'a mut Random) -> Changes<'a> {
+    Box::new(Sidewinder::new(grid, rng))
+}
+
+struct Sidewinder<'a> {
+    grid: &'a mut Grid,
+    rng: &'a mut Random,
+    /// The next cell to look at, going along each row in turn.
+    row: i32,
+    col: i32,
+    run: Vec<Pos>,
+}
+
+impl<'a> Sidewinder<'a> {
+    fn new(grid: &'a mut Grid, rng: &'a mut Random) -> Sidewinder<'a> {
+        Sidewinder { grid, rng, row: 0, col: 0, run: vec![] }
+    }
+}
+
+impl Iterator for Sidewinder<'_> {
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        while self.row < self.grid.rows {
+            let pos = Pos::of(self.row, self.col);
+            self.col += 1;
+            if self.col == self.grid.cols {
+                self.col = 0;
+                self.row += 1;
+            }
+            if !self.grid.has(pos) {
+                continue;
+            }
+            self.run.push(pos);
+
+            let at_east_edge = !self.grid.has(self.grid.east(pos));
+            let at_north_edge = !self.grid.has(self.grid.north(pos));
+            let close_run = at_east_edge || (!at_north_edge && self.rng.rand_below(2) == 0);
+
+            if close_run {
+                let member = self.run[self.rng.rand_below(self.run.len())];
+                let north = self.grid.north(member);
+                self.run.clear();
+                if self.grid.has(north) {
+                    self.grid.link(member, north);
+                    return Some(Change::Link(member, north));
+                }
+            } else {
+                let east = self.grid.east(pos);
+                self.grid.link(pos, east);
+                return Some(Change::Link(pos, east));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,57 @@
+use crate::gen::{Change, Changes};
+use crate::grid::Pos;
+use crate::topology::Topology;
+use crate::Random;
+
+/// Grow the maze outwards from a random cell. Each step, pick a random cell on the edge of the maze
+/// and link it to a random unvisited neighbour. Cells with no unvisited neighbours stop being on the edge.
+/// Makes lots of short dead ends radiating from the start.
+pub fn on<G: Topology + ?Sized>(grid: &mut G, rng: &mut Random) {
+    SimplifiedPrims::new(grid, rng).for_each(drop);
+}
+
+pub fn steps<'a, G: Topology + ?Sized>(grid: &'a mut G, rng: &'a mut Random) -> Changes<'a> {
+    Box::new(SimplifiedPrims::new(grid, rng))
+}
+
+struct SimplifiedPrims<'a, G: ?Sized> {
+    grid: &'a mut G,
+    rng: &'a mut Random,
+    visited: Vec<bool>,
+    active: Vec<Pos>,
+}
+
+impl<'a, G: Topology + ?Sized> SimplifiedPrims<'a, G> {
+    fn new(grid: &'a mut G, rng: &'a mut Random) -> SimplifiedPrims<'a, G> {
+        let start = grid.random_pos(rng);
+        let mut visited = vec![false; grid.capacity()];
+        visited[grid.index(start)] = true;
+        SimplifiedPrims { grid, rng, visited, active: vec![start] }
+    }
+}
+
+impl<G: Topology + ?Sized> Iterator for SimplifiedPrims<'_, G> {
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        while !self.active.is_empty() {
+            let i = self.rng.rand_below(self.active.len());
+            let pos = self.active[i];
+            let unvisited: Vec<Pos> = self.grid.neighbours(pos)
+                .into_iter()
+                .filter(|check| !self.visited[self.grid.index(*check)])
+                .collect();
+
+            if unvisited.is_empty() {
+                self.active.swap_remove(i);
+            } else {
+                let next = unvisited[self.rng.rand_below(unvisited.len())];
+                self.grid.link(pos, next);
+                self.visited[self.grid.index(next)] = true;
+                self.active.push(next);
+                return Some(Change::Link(pos, next));
+            }
+        }
+        None
+    }
+}
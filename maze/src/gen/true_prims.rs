@@ -0,0 +1,62 @@
+use crate::gen::{Change, Changes};
+use crate::grid::Pos;
+use crate::topology::Topology;
+use crate::Random;
+
+/// Like simplified Prim's but every cell gets a random cost up front and instead of choosing randomly,
+/// always grow from the cheapest cell on the edge of the maze into its cheapest unvisited neighbour.
+/// Makes a more star shaped maze with even more dead ends.
+pub fn on<G: Topology + ?Sized>(grid: &mut G, rng: &mut Random) {
+    TruePrims::new(grid, rng).for_each(drop);
+}
+
+pub fn steps<'a, G: Topology + ?Sized>(grid: &'a mut G, rng: &'a mut Random) -> Changes<'a> {
+    Box::new(TruePrims::new(grid, rng))
+}
+
+struct TruePrims<'a, G: ?Sized> {
+    grid: &'a mut G,
+    costs: Vec<usize>,
+    visited: Vec<bool>,
+    active: Vec<Pos>,
+}
+
+impl<'a, G: Topology + ?Sized> TruePrims<'a, G> {
+    fn new(grid: &'a mut G, rng: &mut Random) -> TruePrims<'a, G> {
+        let costs: Vec<usize> = (0..grid.capacity()).map(|_| rng.rand_below(100)).collect();
+        let start = grid.random_pos(rng);
+        let mut visited = vec![false; grid.capacity()];
+        visited[grid.index(start)] = true;
+        TruePrims { grid, costs, visited, active: vec![start] }
+    }
+}
+
+impl<G: Topology + ?Sized> Iterator for TruePrims<'_, G> {
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        while !self.active.is_empty() {
+            let (i, &pos) = self.active.iter()
+                .enumerate()
+                .min_by_key(|(_, pos)| self.costs[self.grid.index(**pos)])
+                .unwrap();
+            let next = self.grid.neighbours(pos)
+                .into_iter()
+                .filter(|check| !self.visited[self.grid.index(*check)])
+                .min_by_key(|check| self.costs[self.grid.index(*check)]);
+
+            match next {
+                None => {
+                    self.active.swap_remove(i);
+                }
+                Some(next) => {
+                    self.grid.link(pos, next);
+                    self.visited[self.grid.index(next)] = true;
+                    self.active.push(next);
+                    return Some(Change::Link(pos, next));
+                }
+            }
+        }
+        None
+    }
+}
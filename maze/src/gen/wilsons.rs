@@ -0,0 +1,78 @@
+use std::collections::VecDeque;
+use crate::gen::{Change, Changes};
+use crate::grid::Pos;
+use crate::topology::Topology;
+use crate::Random;
+
+/// Start with one random cell in the maze. Then randomly walk from an unvisited cell until
+/// you hit the maze, erasing any loops in the walk as you go, and carve the walk into the maze.
+/// Unbiased like Aldous-Broder but slow at the start instead of the end.
+/// If a mask splits the grid into separate areas, only the one it starts in is filled.
+pub fn on<G: Topology + ?Sized>(grid: &mut G, rng: &mut Random) {
+    Wilsons::new(grid, rng).for_each(drop);
+}
+
+pub fn steps<'a, G: Topology + ?Sized>(grid: &'a mut G, rng: &'a mut Random) -> Changes<'a> {
+    Box::new(Wilsons::new(grid, rng))
+}
+
+struct Wilsons<'a, G: ?Sized> {
+    grid: &'a mut G,
+    rng: &'a mut Random,
+    in_maze: Vec<bool>,
+    unvisited: Vec<Pos>,
+    /// The rest of the last walk, waiting to be carved.
+    carving: VecDeque<(Pos, Pos)>,
+}
+
+impl<'a, G: Topology + ?Sized> Wilsons<'a, G> {
+    fn new(grid: &'a mut G, rng: &'a mut Random) -> Wilsons<'a, G> {
+        let mut in_maze = vec![false; grid.capacity()];
+        let mut unvisited: Vec<Pos> = grid.positions();
+        let first = unvisited.swap_remove(rng.rand_below(unvisited.len()));
+        in_maze[grid.index(first)] = true;
+
+        // Walks from a separate area would never find the maze.
+        let mut in_area = vec![false; grid.capacity()];
+        for pos in grid.reachable(first) {
+            in_area[grid.index(pos)] = true;
+        }
+        unvisited.retain(|pos| in_area[grid.index(*pos)]);
+        Wilsons { grid, rng, in_maze, unvisited, carving: VecDeque::new() }
+    }
+
+    /// Walks from a random unvisited cell until it reaches the maze and adds it to the maze.
+    fn walk(&mut self) {
+        let mut pos = self.unvisited[self.rng.rand_below(self.unvisited.len())];
+        let mut path = vec![pos];
+        while !self.in_maze[self.grid.index(pos)] {
+            let neighbours = self.grid.neighbours(pos);
+            pos = neighbours[self.rng.rand_below(neighbours.len())];
+            match path.iter().position(|check| *check == pos) {
+                Some(loop_start) => path.truncate(loop_start + 1),
+                None => path.push(pos),
+            }
+        }
+
+        for pair in path.windows(2) {
+            self.carving.push_back((pair[0], pair[1]));
+            self.in_maze[self.grid.index(pair[0])] = true;
+        }
+        let (grid, in_maze) = (&self.grid, &self.in_maze);
+        self.unvisited.retain(|pos| !in_maze[grid.index(*pos)]);
+    }
+}
+
+impl<G: Topology + ?Sized> Iterator for Wilsons<'_, G> {
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        while self.carving.is_empty() && !self.unvisited.is_empty() {
+            self.walk();
+        }
+
+        let (a, b) = self.carving.pop_front()?;
+        self.grid.link(a, b);
+        Some(Change::Link(a, b))
+    }
+}
@@ -1,96 +1,362 @@
+use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::num::NonZeroU16;
+use std::ops::Deref;
+use crate::mask::Mask;
+use crate::topology::{link_errors, Topology};
+use crate::Random;
 
+#[derive(Clone)]
 pub struct Grid {
     pub rows: i32,
     pub cols: i32,
-    pub cells: Vec<Cell>
+    pub cells: Vec<Cell>,
+    pub mask: Mask,
+    /// The east edge is next to the west edge, like a cylinder.
+    pub wrap_east_west: bool,
+    /// The north edge is next to the south edge. If it also wraps east to west, it's a torus.
+    pub wrap_north_south: bool,
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
 pub struct Pos {
+    /// Which floor the cell is on. Always 0 except in grids with more than one level.
+    pub level: i32,
     pub row: i32,
     pub col: i32
 }
 
 impl Pos {
     pub fn of(row: i32, col: i32) -> Pos {
-        Pos { row, col }
+        Pos::at(0, row, col)
+    }
+
+    pub fn at(level: i32, row: i32, col: i32) -> Pos {
+        Pos { level, row, col }
     }
 }
 
+/// The passages out of a cell, stored as one bit for each direction so huge grids stay small.
+/// What each direction means depends on the shape of the grid (see Topology::directions).
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
 pub struct Cell {
-    pub pos: Pos,
-    pub links: Vec<Pos>
+    links: u16,
+    /// None for a normal cell that costs 1 to walk into.
+    cost: Option<NonZeroU16>,
 }
 
-impl PartialEq for Cell {
-    fn eq(&self, other: &Cell) -> bool {
-        self.pos == other.pos
+impl Cell {
+    /// The most directions a cell can have.
+    pub const MAX_DIRECTIONS: usize = 16;
+
+    pub fn is_linked(&self, direction: usize) -> bool {
+        self.links & (1 << direction) != 0
+    }
+
+    pub fn link(&mut self, direction: usize) {
+        self.links |= 1 << direction;
+    }
+
+    pub fn unlink(&mut self, direction: usize) {
+        self.links &= !(1 << direction);
+    }
+
+    pub fn link_count(&self) -> usize {
+        self.links.count_ones() as usize
+    }
+
+    /// How much it costs to walk into this cell. Cells full of mud or lava cost more than the usual 1.
+    pub fn cost(&self) -> i32 {
+        self.cost.map_or(1, |cost| cost.get() as i32)
+    }
+
+    pub fn is_weighted(&self) -> bool {
+        self.cost.is_some()
+    }
+
+    /// A cost of 1 makes it a normal cell again.
+    pub fn set_cost(&mut self, cost: u16) {
+        assert!(cost > 0, "Cells can't be free to walk into.");
+        self.cost = NonZeroU16::new(cost).filter(|cost| cost.get() != 1);
+    }
+}
+
+/// The cells linked to one cell. Kept on the stack so looking at links doesn't allocate.
+#[derive(Clone, Copy)]
+pub struct Links {
+    positions: [Pos; Cell::MAX_DIRECTIONS],
+    len: usize,
+}
+
+impl Links {
+    pub fn new() -> Links {
+        Links {
+            positions: [Pos::of(0, 0); Cell::MAX_DIRECTIONS],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, pos: Pos) {
+        self.positions[self.len] = pos;
+        self.len += 1;
     }
 }
 
+impl Default for Links {
+    fn default() -> Links {
+        Links::new()
+    }
+}
+
+impl Deref for Links {
+    type Target = [Pos];
+
+    fn deref(&self) -> &[Pos] {
+        &self.positions[..self.len]
+    }
+}
+
+impl IntoIterator for Links {
+    type Item = Pos;
+    type IntoIter = std::iter::Take<std::array::IntoIter<Pos, { Cell::MAX_DIRECTIONS }>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.positions.into_iter().take(self.len)
+    }
+}
+
+impl FromIterator<Pos> for Links {
+    fn from_iter<T: IntoIterator<Item = Pos>>(iter: T) -> Links {
+        let mut links = Links::new();
+        for pos in iter {
+            links.push(pos);
+        }
+        links
+    }
+}
+
+// Directions of the bits in a Grid cell. Flipping the lowest bit gives the opposite direction.
+const NORTH: usize = 0;
+const SOUTH: usize = 1;
+const EAST: usize = 2;
+const WEST: usize = 3;
+
 impl Grid {
     pub fn new(width: i32, height: i32) -> Grid {
-        let mut grid = Grid {
-            rows: height,
-            cols: width,
-            cells: Vec::new()
-        };
-        grid.prepare_grid();
-        grid
+        Grid::masked(Mask::new(height, width))
     }
 
-    fn prepare_grid(&mut self) {
-        self.cells.reserve_exact((self.rows * self.cols) as usize);
-        for row in 0..self.rows {
-            for col in 0..self.cols {
-                self.cells.push(Cell {
-                    pos: Pos::of(row, col),
-                    links: vec![]
-                });
-            }
+    /// A grid the same size as the mask where only the cells that are on can be part of the maze.
+    pub fn masked(mask: Mask) -> Grid {
+        Grid {
+            rows: mask.rows,
+            cols: mask.cols,
+            cells: vec![Cell::default(); (mask.rows * mask.cols) as usize],
+            mask,
+            wrap_east_west: false,
+            wrap_north_south: false,
         }
     }
 
+    /// Makes moving off one edge of the grid come back on the opposite edge.
+    /// Needs at least three cells in the wrapping direction so a cell's two neighbours are different.
+    pub fn wrapping(mut self, east_west: bool, north_south: bool) -> Grid {
+        assert!(!east_west || self.cols >= 3, "Need at least 3 columns to wrap east to west.");
+        assert!(!north_south || self.rows >= 3, "Need at least 3 rows to wrap north to south.");
+        self.wrap_east_west = east_west;
+        self.wrap_north_south = north_south;
+        self
+    }
+
     pub fn north(&self, pos: Pos) -> Pos {
-        Pos::of(pos.row - 1, pos.col)
+        self.wrap(Pos::of(pos.row - 1, pos.col))
     }
 
     pub fn south(&self, pos: Pos) -> Pos {
-        Pos::of(pos.row + 1, pos.col)
+        self.wrap(Pos::of(pos.row + 1, pos.col))
     }
 
     pub fn east(&self, pos: Pos) -> Pos {
-        Pos::of(pos.row, pos.col + 1)
+        self.wrap(Pos::of(pos.row, pos.col + 1))
     }
 
     pub fn west(&self, pos: Pos) -> Pos {
-        Pos::of(pos.row, pos.col - 1)
+        self.wrap(Pos::of(pos.row, pos.col - 1))
+    }
+
+    /// Moves a pos that went off a wrapping edge back onto the other side.
+    fn wrap(&self, mut pos: Pos) -> Pos {
+        if self.wrap_east_west {
+            pos.col = pos.col.rem_euclid(self.cols);
+        }
+        if self.wrap_north_south {
+            pos.row = pos.row.rem_euclid(self.rows);
+        }
+        pos
     }
 
+    /// Is the pos inside the grid and not masked off.
     pub fn has(&self, pos: Pos) -> bool {
+        self.in_bounds(pos) && self.mask.get(pos)
+    }
+
+    fn in_bounds(&self, pos: Pos) -> bool {
         pos.row >= 0 && pos.row < self.rows && pos.col < self.cols && pos.col >= 0
     }
 
-    pub fn mut_cell(&mut self, pos: Pos) -> &mut Cell {
-        if !self.has(pos) {
+    /// The index of a position in the cells vec. Masked off cells still have a slot.
+    pub fn index(&self, pos: Pos) -> usize {
+        if !self.in_bounds(pos) {
             panic!("Invalid pos {:?}", pos);
         }
 
-        let index = (pos.row * self.cols) + pos.col;
-        &mut self.cells[index as usize]
+        ((pos.row * self.cols) + pos.col) as usize
+    }
+
+    pub fn mut_cell(&mut self, pos: Pos) -> &mut Cell {
+        let index = self.index(pos);
+        &mut self.cells[index]
     }
 
     pub fn get_cell(&self, pos: Pos) -> &Cell {
-        if !self.has(pos) {
-            panic!("Invalid pos {:?}", pos);
+        &self.cells[self.index(pos)]
+    }
+
+    /// The cell next to a pos in each direction, in the order of the bits in a Cell.
+    pub fn directions(&self, pos: Pos) -> [Pos; 4] {
+        [self.north(pos), self.south(pos), self.east(pos), self.west(pos)]
+    }
+
+    /// Which bit of a's cell links it to b, if they're next to each other.
+    fn direction(&self, a: Pos, b: Pos) -> Option<usize> {
+        self.directions(a).into_iter().position(|pos| pos == b)
+    }
+
+    fn linking_direction(&self, a: Pos, b: Pos) -> usize {
+        self.direction(a, b).unwrap_or_else(|| panic!("Can't link {:?} to {:?} because they aren't next to each other", a, b))
+    }
+
+    pub fn links(&self, pos: Pos) -> Links {
+        let cell = self.get_cell(pos);
+        let directions = self.directions(pos);
+        (NORTH..=WEST).filter(|direction| cell.is_linked(*direction)).map(|direction| directions[direction]).collect()
+    }
+
+    pub fn is_linked(&self, a: Pos, b: Pos) -> bool {
+        self.has(a) && self.direction(a, b).is_some_and(|direction| self.get_cell(a).is_linked(direction))
+    }
+
+    /// Connect two cells with a passage in both directions.
+    pub fn link(&mut self, a: Pos, b: Pos) {
+        let direction = self.linking_direction(a, b);
+        self.mut_cell(a).link(direction);
+        self.mut_cell(b).link(direction ^ 1);
+    }
+
+    pub fn unlink(&mut self, a: Pos, b: Pos) {
+        let direction = self.linking_direction(a, b);
+        self.mut_cell(a).unlink(direction);
+        self.mut_cell(b).unlink(direction ^ 1);
+    }
+
+    /// The cells next to a pos that could be linked to it, whether or not they are.
+    pub fn neighbours(&self, pos: Pos) -> Vec<Pos> {
+        self.directions(pos).into_iter().filter(|check| self.has(*check)).collect()
+    }
+
+    /// Every cell that isn't masked off.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + '_ {
+        let cols = self.cols;
+        (0..self.rows).flat_map(move |row| (0..cols).map(move |col| Pos::of(row, col))).filter(|pos| self.mask.get(*pos))
+    }
+
+    /// Only has to look through the positions if some are masked off.
+    pub fn random_pos(&self, rng: &mut Random) -> Pos {
+        let size = self.size();
+        let i = rng.rand_below(size);
+        if size == (self.rows * self.cols) as usize {
+            Pos::of(i as i32 / self.cols, i as i32 % self.cols)
+        } else {
+            self.positions().nth(i).unwrap()
         }
+    }
+
+    /// Checks every link goes both ways between two cells in the grid, including that masked off cells have none.
+    pub fn validate(&self) -> Result<(), Vec<GridError>> {
+        let cols = self.cols;
+        link_errors(self, (0..self.rows).flat_map(move |row| (0..cols).map(move |col| Pos::of(row, col))))
+    }
+
+    /// The number of cells that aren't masked off.
+    pub fn size(&self) -> usize {
+        self.mask.count()
+    }
+}
+
+impl Topology for Grid {
+    fn capacity(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn index(&self, pos: Pos) -> usize {
+        Grid::index(self, pos)
+    }
 
-        let index = (pos.row * self.cols) + pos.col;
-        &self.cells[index as usize]
+    fn has(&self, pos: Pos) -> bool {
+        Grid::has(self, pos)
     }
 
-    pub fn to_string(&self) -> String {
+    fn positions(&self) -> Vec<Pos> {
+        Grid::positions(self).collect()
+    }
+
+    fn neighbours(&self, pos: Pos) -> Vec<Pos> {
+        Grid::neighbours(self, pos)
+    }
+
+    fn directions(&self, pos: Pos) -> Vec<Pos> {
+        Grid::directions(self, pos).to_vec()
+    }
+
+    fn get_cell(&self, pos: Pos) -> &Cell {
+        Grid::get_cell(self, pos)
+    }
+
+    fn mut_cell(&mut self, pos: Pos) -> &mut Cell {
+        Grid::mut_cell(self, pos)
+    }
+
+    fn links(&self, pos: Pos) -> Links {
+        Grid::links(self, pos)
+    }
+
+    fn link(&mut self, a: Pos, b: Pos) {
+        Grid::link(self, a, b)
+    }
+
+    fn unlink(&mut self, a: Pos, b: Pos) {
+        Grid::unlink(self, a, b)
+    }
+
+    fn is_linked(&self, a: Pos, b: Pos) -> bool {
+        Grid::is_linked(self, a, b)
+    }
+
+    fn size(&self) -> usize {
+        Grid::size(self)
+    }
+
+    fn random_pos(&self, rng: &mut Random) -> Pos {
+        Grid::random_pos(self, rng)
+    }
+
+    fn validate(&self) -> Result<(), Vec<GridError>> {
+        Grid::validate(self)
+    }
+}
+
+impl Display for Grid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let chars_per_row = 2 + (4 * self.cols) as usize;
         let total_chars = chars_per_row * self.rows as usize;
         let mut result = String::with_capacity(total_chars);
@@ -109,28 +375,253 @@ impl Grid {
             bottom.push('|');
             for col in 0..self.cols {
                 let pos = Pos::of(row, col);
-                if self.get_cell(pos).links.contains(&self.east(pos)) {
+                if self.get_cell(pos).is_linked(EAST) {
                     top.push_str("    ");
                 } else {
                     top.push_str("   |");
                 }
-                if self.get_cell(pos).links.contains(&self.south(pos)) {
+                if self.get_cell(pos).is_linked(SOUTH) {
                     bottom.push_str("   +");
                 } else {
                     bottom.push_str("---+");
                 }
             }
-            result.push_str(&*top);
+            result.push_str(&top);
             result.push('\n');
-            result.push_str(&*bottom);
+            result.push_str(&bottom);
             result.push('\n');
         }
-        result
+        write!(f, "{}", result)
     }
 }
 
-impl Display for Grid {
+/// Where and why reading a maze failed. Lines and columns start at 1.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn at(line: usize, column: usize, message: impl Into<String>) -> ParseError {
+        ParseError { line, column, message: message.into() }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+/// A link that shouldn't be there, found by validating a grid.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum GridError {
+    /// One end of the link is masked off or past an edge that doesn't wrap.
+    NotInGrid { from: Pos, to: Pos },
+    /// From is linked to to but not the other way around.
+    OneWay { from: Pos, to: Pos },
+}
+
+impl Display for GridError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.to_string())
+        match self {
+            GridError::NotInGrid { from, to } => write!(f, "{:?} is linked to {:?} but they aren't both in the grid", from, to),
+            GridError::OneWay { from, to } => write!(f, "{:?} is linked to {:?} but not the other way around", from, to),
+        }
+    }
+}
+
+impl Error for GridError {}
+
+impl Grid {
+    /// Reads a maze in the format written by Display, so saved or hand drawn mazes can be loaded.
+    /// Passages out of the east or south edge make the grid wrap in that direction.
+    /// Masks aren't saved so masked off cells come back as closed in cells.
+    pub fn from_ascii(text: &str) -> Result<Grid, ParseError> {
+        let lines: Vec<Vec<char>> = text.lines().map(|line| line.chars().collect()).collect();
+        let line_count = lines.iter().rposition(|line| !line.is_empty()).map_or(0, |last| last + 1);
+        if line_count == 0 {
+            return Err(ParseError::at(1, 1, "Expected a maze but the text is empty"));
+        }
+
+        let top = &lines[0];
+        if top.len() < 5 || !(top.len() - 1).is_multiple_of(4) {
+            return Err(ParseError::at(1, top.len() + 1, "Expected the top wall to be '+' followed by '---+' for each column"));
+        }
+        let cols = ((top.len() - 1) / 4) as i32;
+        let expected_top = "+".chars().chain("---+".chars().cycle());
+        for (i, (found, expected)) in top.iter().zip(expected_top).enumerate() {
+            if *found != expected {
+                return Err(ParseError::at(1, i + 1, format!("Expected '{}' but found '{}'", expected, found)));
+            }
+        }
+
+        if line_count == 1 {
+            return Err(ParseError::at(2, 1, "Expected at least one row of cells below the top wall"));
+        }
+        if line_count % 2 == 0 {
+            return Err(ParseError::at(line_count + 1, 1, "Expected another line of walls below the last row"));
+        }
+        let rows = ((line_count - 1) / 2) as i32;
+
+        let width = top.len();
+        for (i, line) in lines[1..line_count].iter().enumerate() {
+            let line_number = i + 2;
+            if line.len() != width {
+                let column = line.len().min(width) + 1;
+                return Err(ParseError::at(line_number, column, format!("Expected {} characters to match the top wall but found {}", width, line.len())));
+            }
+            for (col, cell) in line[1..].chunks(4).enumerate() {
+                let column = 2 + (col * 4);
+                let east_side = i % 2 == 0;
+                let expected = match (east_side, cell) {
+                    (true, [' ', ' ', ' ', ' ' | '|']) => None,
+                    (true, _) => Some("'   |' or '    '"),
+                    (false, ['-', '-', '-', '+'] | [' ', ' ', ' ', '+']) => None,
+                    (false, _) => Some("'---+' or '   +'"),
+                };
+                if let Some(expected) = expected {
+                    return Err(ParseError::at(line_number, column, format!("Expected {} for a cell", expected)));
+                }
+            }
+            let first = if i % 2 == 0 { '+' } else { '|' };
+            if line[0] != first {
+                return Err(ParseError::at(line_number, 1, format!("Expected the line to start with '{}'", first)));
+            }
+        }
+
+        // Every character in a cell has been checked so the passages only need to look at one of them.
+        let open_east = |row: i32, col: i32| lines[1 + (2 * row as usize)][4 * (col as usize + 1)] == ' ';
+        let open_south = |row: i32, col: i32| lines[2 + (2 * row as usize)][1 + (4 * col as usize)] == ' ';
+
+        let wrap_east_west = (0..rows).any(|row| open_east(row, cols - 1));
+        let wrap_north_south = (0..cols).any(|col| open_south(rows - 1, col));
+        if wrap_east_west && cols < 3 {
+            let row = (0..rows).find(|row| open_east(*row, cols - 1)).unwrap();
+            return Err(ParseError::at(2 + (2 * row as usize), width, "Passages out of the east edge need at least 3 columns to wrap around"));
+        }
+        if wrap_north_south && rows < 3 {
+            let col = (0..cols).find(|col| open_south(rows - 1, *col)).unwrap();
+            return Err(ParseError::at(line_count, 2 + (4 * col as usize), "Passages out of the south edge need at least 3 rows to wrap around"));
+        }
+
+        let mut grid = Grid::new(cols, rows).wrapping(wrap_east_west, wrap_north_south);
+        for row in 0..rows {
+            for col in 0..cols {
+                let pos = Pos::of(row, col);
+                if open_east(row, col) {
+                    grid.link(pos, grid.east(pos));
+                }
+                if open_south(row, col) {
+                    grid.link(pos, grid.south(pos));
+                }
+            }
+        }
+        Ok(grid)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen::GENERATORS;
+
+    #[test]
+    fn round_trip() {
+        for generator in GENERATORS {
+            let mut grid = Grid::new(7, 5);
+            (generator.on)(&mut grid, &mut Random::new(2));
+            let text = grid.to_string();
+            let parsed = Grid::from_ascii(&text).unwrap();
+            assert_eq!((parsed.rows, parsed.cols), (5, 7));
+            assert_eq!(parsed.to_string(), text, "{}", generator.name);
+            for pos in grid.positions() {
+                for other in grid.neighbours(pos) {
+                    assert_eq!(grid.is_linked(pos, other), parsed.is_linked(pos, other));
+                }
+            }
+        }
+    }
+
+    /// Big grids are mostly cells so they have to stay tiny.
+    #[test]
+    fn cell_size() {
+        assert!(std::mem::size_of::<Cell>() <= 4);
+        assert_eq!(Grid::new(2000, 2000).size(), 4_000_000);
+
+        // Picking straight from the rows and cols chooses the same cell as counting through the positions.
+        let grid = Grid::new(7, 5);
+        let (mut a, mut b) = (Random::new(1), Random::new(1));
+        for _ in 0..10 {
+            assert_eq!(grid.random_pos(&mut a), grid.positions().nth(b.rand_below(grid.size())).unwrap());
+        }
+    }
+
+    #[test]
+    fn links() {
+        let mut grid = Grid::new(3, 3).wrapping(true, false);
+        let pos = Pos::of(1, 0);
+        grid.link(pos, grid.west(pos));
+        grid.link(pos, grid.south(pos));
+        assert_eq!(grid.links(pos).to_vec(), vec![Pos::of(2, 0), Pos::of(1, 2)]);
+        assert_eq!(grid.links(Pos::of(1, 2)).to_vec(), vec![pos]);
+        assert!(grid.is_linked(Pos::of(1, 2), pos));
+        assert!(!grid.is_linked(pos, grid.north(pos)));
+
+        grid.unlink(grid.west(pos), pos);
+        assert_eq!(grid.links(pos).to_vec(), vec![Pos::of(2, 0)]);
+        assert!(grid.links(Pos::of(1, 2)).is_empty());
+    }
+
+    #[test]
+    fn validate() {
+        let mut mask = Mask::new(3, 3);
+        mask.set(Pos::of(2, 2), false);
+        let mut grid = Grid::masked(mask);
+        grid.link(Pos::of(0, 0), Pos::of(0, 1));
+        assert_eq!(grid.validate(), Ok(()));
+        assert!(!grid.is_perfect());
+        assert_eq!(grid.connected_components().len(), 7);
+
+        grid.mut_cell(Pos::of(1, 1)).link(EAST);
+        grid.mut_cell(Pos::of(0, 2)).link(EAST);
+        grid.mut_cell(Pos::of(2, 2)).link(NORTH);
+        assert_eq!(grid.validate(), Err(vec![
+            GridError::NotInGrid { from: Pos::of(0, 2), to: Pos::of(0, 3) },
+            GridError::OneWay { from: Pos::of(1, 1), to: Pos::of(1, 2) },
+            GridError::NotInGrid { from: Pos::of(2, 2), to: Pos::of(1, 2) },
+        ]));
+    }
+
+    #[test]
+    fn wrapping() {
+        let mut grid = Grid::new(4, 3).wrapping(true, true);
+        crate::gen::recursive_backtracker::on(&mut grid, &mut Random::new(3));
+        let parsed = Grid::from_ascii(&grid.to_string()).unwrap();
+        assert_eq!(parsed.to_string(), grid.to_string());
+        assert!(parsed.wrap_east_west || parsed.wrap_north_south);
+    }
+
+    fn error_at(text: &str) -> (usize, usize) {
+        let error = Grid::from_ascii(text).err().expect("Should not parse");
+        (error.line, error.column)
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error_at(""), (1, 1));
+        assert_eq!(error_at("+---+\n"), (2, 1));
+        assert_eq!(error_at("+---+--+\n+   |\n|---+\n"), (1, 9));
+        assert_eq!(error_at("+---+-x-+\n+       |\n|---+---+\n"), (1, 7));
+        assert_eq!(error_at("+---+---+\n+   | x |\n|---+---+\n"), (2, 6));
+        assert_eq!(error_at("+---+---+\n+   |   |\n|---+--\n"), (3, 8));
+        assert_eq!(error_at("+---+---+\n+   |   |\n"), (3, 1));
+        assert_eq!(error_at("+---+---+\n|   |   |\n|---+---+\n"), (2, 1));
+        assert_eq!(error_at("+---+---+\n+       \n|---+---+\n"), (2, 9));
     }
 }
@@ -0,0 +1,95 @@
+use crate::grid::{Cell, Pos};
+use crate::topology::Topology;
+
+/// A grid of flat topped hexagons. Odd columns are shifted half a cell down so each cell touches six others.
+#[derive(Clone)]
+pub struct HexGrid {
+    pub rows: i32,
+    pub cols: i32,
+    pub cells: Vec<Cell>,
+}
+
+impl HexGrid {
+    pub fn new(rows: i32, cols: i32) -> HexGrid {
+        HexGrid {
+            rows,
+            cols,
+            cells: vec![Cell::default(); (rows * cols) as usize],
+        }
+    }
+
+    /// The row of the cells diagonally above, which depends on whether this column is shifted down.
+    fn north_diagonal(&self, pos: Pos) -> i32 {
+        if pos.col % 2 == 0 { pos.row - 1 } else { pos.row }
+    }
+
+    fn south_diagonal(&self, pos: Pos) -> i32 {
+        if pos.col % 2 == 0 { pos.row } else { pos.row + 1 }
+    }
+
+    pub fn north(&self, pos: Pos) -> Pos {
+        Pos::of(pos.row - 1, pos.col)
+    }
+
+    pub fn south(&self, pos: Pos) -> Pos {
+        Pos::of(pos.row + 1, pos.col)
+    }
+
+    pub fn north_east(&self, pos: Pos) -> Pos {
+        Pos::of(self.north_diagonal(pos), pos.col + 1)
+    }
+
+    pub fn north_west(&self, pos: Pos) -> Pos {
+        Pos::of(self.north_diagonal(pos), pos.col - 1)
+    }
+
+    pub fn south_east(&self, pos: Pos) -> Pos {
+        Pos::of(self.south_diagonal(pos), pos.col + 1)
+    }
+
+    pub fn south_west(&self, pos: Pos) -> Pos {
+        Pos::of(self.south_diagonal(pos), pos.col - 1)
+    }
+}
+
+impl Topology for HexGrid {
+    fn capacity(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn index(&self, pos: Pos) -> usize {
+        if !self.has(pos) {
+            panic!("Invalid pos {:?}", pos);
+        }
+
+        ((pos.row * self.cols) + pos.col) as usize
+    }
+
+    fn has(&self, pos: Pos) -> bool {
+        pos.row >= 0 && pos.row < self.rows && pos.col >= 0 && pos.col < self.cols
+    }
+
+    fn positions(&self) -> Vec<Pos> {
+        (0..self.rows).flat_map(|row| (0..self.cols).map(move |col| Pos::of(row, col))).collect()
+    }
+
+    fn neighbours(&self, pos: Pos) -> Vec<Pos> {
+        [
+            self.north(pos),
+            self.north_east(pos),
+            self.south_east(pos),
+            self.south(pos),
+            self.south_west(pos),
+            self.north_west(pos),
+        ].into_iter().filter(|check| self.has(*check)).collect()
+    }
+
+    fn get_cell(&self, pos: Pos) -> &Cell {
+        &self.cells[self.index(pos)]
+    }
+
+    fn mut_cell(&mut self, pos: Pos) -> &mut Cell {
+        let index = self.index(pos);
+        &mut self.cells[index]
+    }
+}
@@ -0,0 +1,116 @@
+use crate::grid::{Cell, Grid, Pos};
+use crate::topology::Topology;
+
+/// Rectangular levels stacked on top of each other.
+/// As well as the usual four directions, cells can link up or down to the cell in the same place on the next level.
+#[derive(Clone)]
+pub struct LayeredGrid {
+    pub levels: i32,
+    pub rows: i32,
+    pub cols: i32,
+    pub cells: Vec<Cell>,
+}
+
+impl LayeredGrid {
+    pub fn new(levels: i32, rows: i32, cols: i32) -> LayeredGrid {
+        LayeredGrid {
+            levels,
+            rows,
+            cols,
+            cells: vec![Cell::default(); (levels * rows * cols) as usize],
+        }
+    }
+
+    pub fn north(&self, pos: Pos) -> Pos {
+        Pos::at(pos.level, pos.row - 1, pos.col)
+    }
+
+    pub fn south(&self, pos: Pos) -> Pos {
+        Pos::at(pos.level, pos.row + 1, pos.col)
+    }
+
+    pub fn east(&self, pos: Pos) -> Pos {
+        Pos::at(pos.level, pos.row, pos.col + 1)
+    }
+
+    pub fn west(&self, pos: Pos) -> Pos {
+        Pos::at(pos.level, pos.row, pos.col - 1)
+    }
+
+    pub fn up(&self, pos: Pos) -> Pos {
+        Pos::at(pos.level + 1, pos.row, pos.col)
+    }
+
+    pub fn down(&self, pos: Pos) -> Pos {
+        Pos::at(pos.level - 1, pos.row, pos.col)
+    }
+
+    /// A flat grid of one level with only the passages that stay on that level.
+    pub fn level(&self, level: i32) -> Grid {
+        let mut grid = Grid::new(self.cols, self.rows);
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let pos = Pos::at(level, row, col);
+                grid.mut_cell(Pos::of(row, col)).set_cost(self.get_cell(pos).cost() as u16);
+                for other in [self.east(pos), self.south(pos)] {
+                    if self.is_linked(pos, other) {
+                        grid.link(Pos::of(row, col), Pos::of(other.row, other.col));
+                    }
+                }
+            }
+        }
+        grid
+    }
+}
+
+impl Topology for LayeredGrid {
+    fn capacity(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn index(&self, pos: Pos) -> usize {
+        if !self.has(pos) {
+            panic!("Invalid pos {:?}", pos);
+        }
+
+        (((pos.level * self.rows) + pos.row) * self.cols + pos.col) as usize
+    }
+
+    fn has(&self, pos: Pos) -> bool {
+        pos.level >= 0 && pos.level < self.levels
+            && pos.row >= 0 && pos.row < self.rows
+            && pos.col >= 0 && pos.col < self.cols
+    }
+
+    fn positions(&self) -> Vec<Pos> {
+        let mut positions = Vec::with_capacity(self.cells.len());
+        for level in 0..self.levels {
+            for row in 0..self.rows {
+                for col in 0..self.cols {
+                    positions.push(Pos::at(level, row, col));
+                }
+            }
+        }
+        positions
+    }
+
+    fn neighbours(&self, pos: Pos) -> Vec<Pos> {
+        [
+            self.north(pos),
+            self.south(pos),
+            self.east(pos),
+            self.west(pos),
+            self.up(pos),
+            self.down(pos),
+        ].into_iter().filter(|check| self.has(*check)).collect()
+    }
+
+    fn get_cell(&self, pos: Pos) -> &Cell {
+        &self.cells[self.index(pos)]
+    }
+
+    fn mut_cell(&mut self, pos: Pos) -> &mut Cell {
+        let index = self.index(pos);
+        &mut self.cells[index]
+    }
+}
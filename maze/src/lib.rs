@@ -1,14 +1,68 @@
 pub mod grid;
 pub mod gen;
 pub mod solve;
+pub mod mask;
+pub mod topology;
+pub mod polar;
+pub mod hex;
+pub mod triangle;
+pub mod layered;
+pub mod weave;
+pub mod export;
+pub mod analysis;
 
-use rand::{thread_rng, Rng};
-pub use grid::{Grid, Cell, Pos};
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+pub use grid::{Grid, Cell, Links, Pos, ParseError, GridError};
+pub use mask::Mask;
+pub use topology::Topology;
+pub use polar::PolarGrid;
+pub use hex::HexGrid;
+pub use triangle::TriangleGrid;
+pub use layered::LayeredGrid;
+pub use weave::WeaveGrid;
 
-pub fn rand() -> usize {
-    rand_below(usize::MAX)
+/// A random number generator that always gives the same numbers for the same seed,
+/// so anything generated with it can be reproduced.
+pub struct Random {
+    seed: u64,
+    rng: StdRng,
 }
 
-pub fn rand_below(max: usize) -> usize {
-    thread_rng().gen_range(usize::MIN..max)
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rand(&mut self) -> usize {
+        self.rand_below(usize::MAX)
+    }
+
+    pub fn rand_below(&mut self, max: usize) -> usize {
+        self.rng.gen_range(usize::MIN..max)
+    }
+
+    /// True with probability p, between 0 and 1.
+    pub fn chance(&mut self, p: f64) -> bool {
+        self.rng.gen::<f64>() < p
+    }
+
+    /// Randomly reorders the items in place (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.rand_below(i + 1));
+        }
+    }
+}
+
+/// A new seed that is actually random. Everything else should come from a Random made with it.
+pub fn random_seed() -> u64 {
+    thread_rng().gen()
 }
@@ -1,7 +1,195 @@
 extern crate maze;
 
+use std::fs;
+use std::io::Write;
+use maze::analysis::Stats;
+use maze::export::{to_ppm, to_svg, ExportOptions};
+use maze::gen::MazeGenerator;
+use maze::solve::a_star::{manhattan, AStar};
+use maze::solve::dead_end_filling::DeadEndFilling;
+use maze::solve::dijkstra::{longest_path, Distances};
+use maze::solve::tremaux::Tremaux;
+use maze::solve::wall_follower::WallFollower;
+use maze::solve::Step;
+use maze::{Grid, Pos};
+
+/// Everything the command line can choose.
+struct Options {
+    width: i32,
+    height: i32,
+    generator: MazeGenerator,
+    seed: u64,
+    format: String,
+    output: Option<String>,
+    cell_size: i32,
+    distances: bool,
+    solution: bool,
+    /// If set, compare every generator over this many mazes instead of making one.
+    analyse: Option<usize>,
+}
+
 fn main() {
-    let mut grid = maze::Grid::new(40, 15);
-    maze::gen::binary_tree::on(&mut grid);
-    println!("{}", grid);
-}
\ No newline at end of file
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            eprintln!(
+                "Usage: maze_main [--size <width>x<height>] [--generator <{}>] [--seed <number>] [--format <ascii|svg|ppm>] [--output <file>] [--cell-size <pixels>] [--distances] [--solution] [--analyse <runs>]",
+                MazeGenerator::names().collect::<Vec<_>>().join("|")
+            );
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(runs) = options.analyse {
+        analyse(&options, runs);
+        return;
+    }
+
+    let mut grid = maze::Grid::new(options.width, options.height);
+    let mut rng = maze::Random::new(options.seed);
+    (options.generator.on)(&mut grid, &mut rng);
+    eprintln!("Generated {}x{} maze with {} (seed {})", options.width, options.height, options.generator.name, options.seed);
+
+    // The solution goes between the two cells furthest apart and distances are measured from its start.
+    let path = longest_path(&grid);
+    let distances = Distances::from(&grid, path[0]);
+    let mut export = ExportOptions::new(options.cell_size);
+    if options.distances {
+        export.distances = Some(&distances);
+    }
+    if options.solution {
+        export.path = &path;
+    }
+
+    let data = match options.format.as_str() {
+        "svg" => to_svg(&grid, &export).into_bytes(),
+        "ppm" => to_ppm(&grid, &export),
+        _ => grid.to_string().into_bytes(),
+    };
+
+    let result = match &options.output {
+        Some(path) => fs::write(path, &data),
+        None => std::io::stdout().write_all(&data),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to write maze: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Prints a table of the average stats for mazes made by each generator,
+/// then how much of those mazes each solver looks at to get from the top left to the bottom right corner.
+fn analyse(options: &Options, runs: usize) {
+    println!("Average of {} {}x{} mazes (seed {})", runs, options.width, options.height, options.seed);
+    println!("{:<22} {:>9} {:>9} {:>11} {:>6} {:>10}", "generator", "dead ends", "solution", "horizontal", "river", "branching");
+    let mut explored = vec![];
+    for generator in maze::gen::GENERATORS {
+        let mut seeds = maze::Random::new(options.seed);
+        let mut all = vec![];
+        let mut solvers = [0.0; 4];
+        for _ in 0..runs {
+            let mut grid = maze::Grid::new(options.width, options.height);
+            (generator.on)(&mut grid, &mut maze::Random::new(seeds.rand() as u64));
+            all.push(Stats::of(&grid));
+
+            let (start, goal) = (Pos::of(0, 0), Pos::of(grid.rows - 1, grid.cols - 1));
+            solvers[0] += fraction_explored(&grid, WallFollower::new(&grid, start, goal));
+            solvers[1] += fraction_explored(&grid, DeadEndFilling::new(&grid, start, goal));
+            solvers[2] += fraction_explored(&grid, Tremaux::new(&grid, start, goal));
+            solvers[3] += fraction_explored(&grid, AStar::new(&grid, start, goal, manhattan(&grid, goal)));
+        }
+        explored.push((generator.name, solvers.map(|total| total / runs as f64)));
+
+        let stats = Stats::mean(&all);
+        println!(
+            "{:<22} {:>9.3} {:>9.3} {:>11.3} {:>6.2} {:>10.3}",
+            generator.name, stats.dead_ends, stats.solution_length, stats.horizontal_bias, stats.river, stats.branching
+        );
+    }
+
+    println!();
+    println!("Fraction of cells each solver looks at");
+    println!("{:<22} {:>13} {:>16} {:>8} {:>6}", "generator", "wall follower", "dead end filling", "tremaux", "a star");
+    for (name, solvers) in explored {
+        println!("{:<22} {:>13.3} {:>16.3} {:>8.3} {:>6.3}", name, solvers[0], solvers[1], solvers[2], solvers[3]);
+    }
+}
+
+/// How many different cells a solver visits or marks as dead ends before it finishes, as a fraction of the grid.
+fn fraction_explored(grid: &Grid, solver: impl Iterator<Item = Step>) -> f64 {
+    let mut seen = vec![false; grid.cells.len()];
+    for step in solver {
+        let (Step::Visit(pos) | Step::DeadEnd(pos)) = step;
+        seen[grid.index(pos)] = true;
+    }
+    seen.iter().filter(|seen| **seen).count() as f64 / grid.size() as f64
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        width: 40,
+        height: 15,
+        generator: maze::gen::GENERATORS[0],
+        seed: maze::random_seed(),
+        format: "ascii".to_string(),
+        output: None,
+        cell_size: 20,
+        distances: false,
+        solution: false,
+        analyse: None,
+    };
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--distances" => {
+                options.distances = true;
+                continue;
+            }
+            "--solution" => {
+                options.solution = true;
+                continue;
+            }
+            _ => {}
+        }
+
+        let value = args.next().ok_or(format!("Missing value for {}", flag))?;
+        match flag.as_str() {
+            "--size" => {
+                let (width, height) = value.split_once('x').ok_or(format!("Invalid size {}, expected <width>x<height>", value))?;
+                options.width = parse_positive(width, "width")?;
+                options.height = parse_positive(height, "height")?;
+            }
+            "--generator" => {
+                options.generator = MazeGenerator::by_name(&value).ok_or(format!("Unknown generator {}", value))?;
+            }
+            "--seed" => {
+                options.seed = value.parse().map_err(|_| format!("Invalid seed {}", value))?;
+            }
+            "--format" => {
+                if !["ascii", "svg", "ppm"].contains(&value.as_str()) {
+                    return Err(format!("Unknown format {}", value));
+                }
+                options.format = value;
+            }
+            "--output" => {
+                options.output = Some(value);
+            }
+            "--cell-size" => {
+                options.cell_size = parse_positive(&value, "cell size")?;
+            }
+            "--analyse" => {
+                options.analyse = Some(parse_positive(&value, "number of runs")? as usize);
+            }
+            _ => return Err(format!("Unknown argument {}", flag)),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_positive(value: &str, name: &str) -> Result<i32, String> {
+    match value.parse() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(format!("Invalid {} {}, expected a positive number", name, value)),
+    }
+}
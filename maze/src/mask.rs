@@ -0,0 +1,207 @@
+use std::fs;
+use std::path::Path;
+use crate::grid::Pos;
+
+/// Masks bigger than this many cells are rejected instead of trying to allocate them.
+const MAX_CELLS: i64 = 4096 * 4096;
+
+/// Which cells of a grid are switched on, so mazes can be generated in shapes other than rectangles.
+/// Cells that are off are never linked to anything.
+#[derive(Clone)]
+pub struct Mask {
+    pub rows: i32,
+    pub cols: i32,
+    bits: Vec<bool>,
+    /// How many bits are on, kept up to date by set so count doesn't have to look at every cell.
+    on: usize,
+}
+
+impl Mask {
+    /// A mask with every cell on.
+    pub fn new(rows: i32, cols: i32) -> Mask {
+        Mask {
+            rows,
+            cols,
+            bits: vec![true; (rows * cols) as usize],
+            on: (rows * cols) as usize,
+        }
+    }
+
+    /// Anything outside the mask counts as off.
+    pub fn get(&self, pos: Pos) -> bool {
+        let inside = pos.row >= 0 && pos.row < self.rows && pos.col >= 0 && pos.col < self.cols;
+        inside && self.bits[((pos.row * self.cols) + pos.col) as usize]
+    }
+
+    pub fn set(&mut self, pos: Pos, on: bool) {
+        let bit = &mut self.bits[((pos.row * self.cols) + pos.col) as usize];
+        if *bit != on {
+            *bit = on;
+            if on { self.on += 1 } else { self.on -= 1 }
+        }
+    }
+
+    /// How many cells are on.
+    pub fn count(&self) -> usize {
+        self.on
+    }
+
+    /// Each line is a row and each character is a cell. An `X` means the cell is off.
+    /// Lines shorter than the longest one are treated as off past their end. Spaces are on, so only empty lines at the end are ignored.
+    pub fn from_ascii(text: &str) -> Result<Mask, String> {
+        let mut lines: Vec<&str> = text.lines().collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        let rows = lines.len() as i32;
+        let cols = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as i32;
+
+        let mut mask = Mask::new(rows, cols);
+        for (row, line) in lines.iter().enumerate() {
+            let mut chars = line.chars();
+            for col in 0..cols {
+                let on = !matches!(chars.next(), None | Some('X') | Some('x'));
+                mask.set(Pos::of(row as i32, col), on);
+            }
+        }
+        mask.check_not_empty()
+    }
+
+    /// Reads a plain (P1) or raw (P4) portable bitmap. Black pixels are off.
+    pub fn from_pbm(data: &[u8]) -> Result<Mask, String> {
+        let mut reader = PbmReader { data, index: 0 };
+        let kind = reader.token()?;
+        let cols: i32 = reader.number()?;
+        let rows: i32 = reader.number()?;
+        if cols <= 0 || rows <= 0 || cols as i64 * rows as i64 > MAX_CELLS {
+            return Err(format!("Invalid PBM size {}x{}, expected at least 1x1 and at most {} pixels", cols, rows, MAX_CELLS));
+        }
+        let mut mask = Mask::new(rows, cols);
+
+        match kind.as_str() {
+            "P1" => {
+                for row in 0..rows {
+                    for col in 0..cols {
+                        let black = match reader.pixel()? {
+                            b'0' => false,
+                            b'1' => true,
+                            other => return Err(format!("Invalid pixel '{}' in PBM", other as char)),
+                        };
+                        mask.set(Pos::of(row, col), !black);
+                    }
+                }
+            }
+            "P4" => {
+                // Exactly one whitespace character separates the header from the pixels.
+                let start = reader.index + 1;
+                let bytes_per_row = ((cols + 7) / 8) as usize;
+                let expected = start + (bytes_per_row * rows as usize);
+                if data.len() < expected {
+                    return Err(format!("PBM is too short, expected {} bytes but found {}", expected, data.len()));
+                }
+
+                for row in 0..rows {
+                    for col in 0..cols {
+                        let byte = data[start + (row as usize * bytes_per_row) + (col / 8) as usize];
+                        let black = (byte >> (7 - (col % 8))) & 1 == 1;
+                        mask.set(Pos::of(row, col), !black);
+                    }
+                }
+            }
+            _ => return Err(format!("Unsupported PBM format {}, expected P1 or P4", kind)),
+        }
+
+        mask.check_not_empty()
+    }
+
+    /// There has to be at least one cell to put the maze in.
+    fn check_not_empty(self) -> Result<Mask, String> {
+        if self.count() == 0 {
+            Err("Mask has every cell switched off".to_string())
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Files ending in .pbm are read as images and anything else as ascii.
+    pub fn from_file(path: &Path) -> Result<Mask, String> {
+        let data = fs::read(path).map_err(|e| format!("Failed to read mask {}: {}", path.display(), e))?;
+        let is_image = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("pbm"));
+        if is_image {
+            Mask::from_pbm(&data)
+        } else {
+            let text = String::from_utf8(data).map_err(|_| format!("Mask {} is not valid text", path.display()))?;
+            Mask::from_ascii(&text)
+        }
+    }
+}
+
+struct PbmReader<'a> {
+    data: &'a [u8],
+    index: usize,
+}
+
+impl<'a> PbmReader<'a> {
+    fn skip_whitespace_and_comments(&mut self) {
+        while self.index < self.data.len() {
+            let c = self.data[self.index];
+            if c == b'#' {
+                while self.index < self.data.len() && self.data[self.index] != b'\n' {
+                    self.index += 1;
+                }
+            } else if c.is_ascii_whitespace() {
+                self.index += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn token(&mut self) -> Result<String, String> {
+        self.skip_whitespace_and_comments();
+        let start = self.index;
+        while self.index < self.data.len() && !self.data[self.index].is_ascii_whitespace() {
+            self.index += 1;
+        }
+        if start == self.index {
+            return Err("Unexpected end of PBM header".to_string());
+        }
+        Ok(String::from_utf8_lossy(&self.data[start..self.index]).to_string())
+    }
+
+    fn number(&mut self) -> Result<i32, String> {
+        let token = self.token()?;
+        token.parse().map_err(|_| format!("Invalid number {} in PBM header", token))
+    }
+
+    /// In plain PBMs the pixels don't have to be separated by whitespace.
+    fn pixel(&mut self) -> Result<u8, String> {
+        self.skip_whitespace_and_comments();
+        let pixel = *self.data.get(self.index).ok_or("PBM ended before all pixels were read")?;
+        self.index += 1;
+        Ok(pixel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors() {
+        assert!(Mask::from_ascii("XX\nX").is_err());
+        assert!(Mask::from_ascii("\n\n").is_err());
+        assert!(Mask::from_pbm(b"P1\n2 1\n1 1").is_err());
+        assert!(Mask::from_pbm(b"P1\n-3 2\n").is_err());
+        assert!(Mask::from_pbm(b"P4\n100000 100000\n").is_err());
+        assert!(Mask::from_pbm(b"P4\n0 5\n").is_err());
+
+        let mask = Mask::from_pbm(b"P4\n3 2\n\x20\x00").unwrap();
+        assert_eq!((mask.cols, mask.rows, mask.count()), (3, 2, 5));
+        assert!(!mask.get(Pos::of(0, 2)));
+
+        let mask = Mask::from_ascii("X X\n   \nX X\n\n").unwrap();
+        assert_eq!((mask.cols, mask.rows, mask.count()), (3, 3, 5));
+        assert!(mask.get(Pos::of(1, 0)));
+    }
+}
@@ -0,0 +1,128 @@
+use std::f64::consts::PI;
+use crate::grid::{Cell, Pos};
+use crate::topology::Topology;
+
+/// A circular grid made of rings around a single centre cell.
+/// For a pos, the row is the ring (0 in the middle) and the col is how far clockwise around the ring it is.
+/// Outer rings are longer so their cells get subdivided to stay roughly square.
+#[derive(Clone)]
+pub struct PolarGrid {
+    pub rows: i32,
+    /// How many cells are in each ring.
+    pub row_sizes: Vec<i32>,
+    /// Index in cells of the first cell in each ring.
+    row_starts: Vec<usize>,
+    pub cells: Vec<Cell>,
+}
+
+impl PolarGrid {
+    pub fn new(rows: i32) -> PolarGrid {
+        let row_height = 1.0 / rows as f64;
+        let mut row_sizes = vec![1];
+        for row in 1..rows {
+            let radius = row as f64 / rows as f64;
+            let circumference = 2.0 * PI * radius;
+            let previous = row_sizes[(row - 1) as usize];
+
+            // If splitting this ring the same as the one inside it would make the cells too wide, split each of them in some number of pieces.
+            let estimated_cell_width = circumference / previous as f64;
+            let ratio = (estimated_cell_width / row_height).round().max(1.0) as i32;
+            row_sizes.push(previous * ratio);
+        }
+
+        let mut row_starts = Vec::with_capacity(rows as usize);
+        let mut count = 0;
+        for size in &row_sizes {
+            row_starts.push(count);
+            count += *size as usize;
+        }
+
+        PolarGrid {
+            rows,
+            row_sizes,
+            row_starts,
+            cells: vec![Cell::default(); count],
+        }
+    }
+
+    pub fn row_size(&self, row: i32) -> i32 {
+        self.row_sizes[row as usize]
+    }
+
+    pub fn cw(&self, pos: Pos) -> Pos {
+        Pos::of(pos.row, (pos.col + 1) % self.row_size(pos.row))
+    }
+
+    pub fn ccw(&self, pos: Pos) -> Pos {
+        let size = self.row_size(pos.row);
+        Pos::of(pos.row, (pos.col - 1 + size) % size)
+    }
+
+    /// The cell in the next ring towards the centre that shares this cell's inner wall.
+    pub fn inward(&self, pos: Pos) -> Option<Pos> {
+        if pos.row == 0 {
+            return None;
+        }
+
+        let ratio = self.row_size(pos.row) / self.row_size(pos.row - 1);
+        Some(Pos::of(pos.row - 1, pos.col / ratio))
+    }
+
+    /// The cells in the next ring out that share this cell's outer wall.
+    pub fn outward(&self, pos: Pos) -> Vec<Pos> {
+        if pos.row == self.rows - 1 {
+            return vec![];
+        }
+
+        let ratio = self.row_size(pos.row + 1) / self.row_size(pos.row);
+        ((pos.col * ratio)..((pos.col + 1) * ratio))
+            .map(|col| Pos::of(pos.row + 1, col))
+            .collect()
+    }
+}
+
+impl Topology for PolarGrid {
+    fn capacity(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn index(&self, pos: Pos) -> usize {
+        if !self.has(pos) {
+            panic!("Invalid pos {:?}", pos);
+        }
+
+        self.row_starts[pos.row as usize] + pos.col as usize
+    }
+
+    fn has(&self, pos: Pos) -> bool {
+        pos.row >= 0 && pos.row < self.rows && pos.col >= 0 && pos.col < self.row_size(pos.row)
+    }
+
+    fn positions(&self) -> Vec<Pos> {
+        (0..self.rows).flat_map(|row| (0..self.row_size(row)).map(move |col| Pos::of(row, col))).collect()
+    }
+
+    fn neighbours(&self, pos: Pos) -> Vec<Pos> {
+        let mut neighbours = vec![];
+        // The centre cell would be its own clockwise neighbour.
+        if self.row_size(pos.row) > 1 {
+            neighbours.push(self.cw(pos));
+            let ccw = self.ccw(pos);
+            if !neighbours.contains(&ccw) {
+                neighbours.push(ccw);
+            }
+        }
+        neighbours.extend(self.inward(pos));
+        neighbours.extend(self.outward(pos));
+        neighbours
+    }
+
+    fn get_cell(&self, pos: Pos) -> &Cell {
+        &self.cells[self.index(pos)]
+    }
+
+    fn mut_cell(&mut self, pos: Pos) -> &mut Cell {
+        let index = self.index(pos);
+        &mut self.cells[index]
+    }
+}
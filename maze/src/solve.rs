@@ -1,2 +1,190 @@
 pub mod dijkstra;
+pub mod wall_follower;
+pub mod dead_end_filling;
+pub mod tremaux;
+pub mod a_star;
 
+use std::collections::VecDeque;
+use crate::grid::Pos;
+use crate::topology::Topology;
+
+/// Something a solver did on its way to the goal, so it can be animated or counted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    /// Moved into or looked at a cell.
+    Visit(Pos),
+    /// Decided a cell isn't on the way to the goal.
+    DeadEnd(Pos),
+}
+
+/// A maze solver that works one step at a time.
+pub trait Solver: Iterator<Item = Step> {
+    /// The cells to walk through to get from the start to the goal, including both ends.
+    /// Empty until the solver has finished or if the goal can't be reached.
+    fn path(&self) -> Vec<Pos>;
+}
+
+/// Runs a solver until it finishes and returns its path.
+pub fn run(mut solver: impl Solver) -> Vec<Pos> {
+    solver.by_ref().for_each(drop);
+    solver.path()
+}
+
+/// The way a solver has walked so far with any loops cut out, so it's always a simple path from the start.
+struct Walk {
+    cells: Vec<Pos>,
+    on_walk: Vec<bool>,
+}
+
+impl Walk {
+    fn new(grid: &dyn Topology, start: Pos) -> Walk {
+        let mut on_walk = vec![false; grid.capacity()];
+        on_walk[grid.index(start)] = true;
+        Walk {
+            cells: vec![start],
+            on_walk,
+        }
+    }
+
+    fn current(&self) -> Pos {
+        *self.cells.last().unwrap()
+    }
+
+    /// Moves to a cell and adds the steps to show it. Coming back to an earlier cell cuts out the loop since then.
+    fn move_to(&mut self, grid: &dyn Topology, pos: Pos, steps: &mut VecDeque<Step>) {
+        if self.on_walk[grid.index(pos)] {
+            while self.current() != pos {
+                let cut = self.cells.pop().unwrap();
+                self.on_walk[grid.index(cut)] = false;
+                steps.push_back(Step::DeadEnd(cut));
+            }
+        } else {
+            self.on_walk[grid.index(pos)] = true;
+            self.cells.push(pos);
+        }
+        steps.push_back(Step::Visit(pos));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen::GENERATORS;
+    use crate::solve::dijkstra::Distances;
+    use crate::{Grid, PolarGrid, Random};
+
+    /// Each cell on the path is linked to the next one.
+    fn assert_walkable(grid: &dyn Topology, path: &[Pos], name: &str) {
+        for pair in path.windows(2) {
+            assert!(grid.is_linked(pair[0], pair[1]), "{} went through a wall from {:?} to {:?}", name, pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn same_path_in_perfect_mazes() {
+        for generator in GENERATORS {
+            let mut grid = Grid::new(8, 6);
+            (generator.on)(&mut grid, &mut Random::new(4));
+            let (start, goal) = (Pos::of(0, 0), Pos::of(5, 7));
+            let expected = Distances::from(&grid, start).path_to(goal);
+            assert_eq!(wall_follower::solve(&grid, start, goal), expected, "wall follower after {}", generator.name);
+            assert_eq!(dead_end_filling::solve(&grid, start, goal), expected, "dead end filling after {}", generator.name);
+            assert_eq!(tremaux::solve(&grid, start, goal), expected, "tremaux after {}", generator.name);
+            assert_eq!(a_star::solve(&grid, start, goal), expected, "a star after {}", generator.name);
+        }
+    }
+
+    #[test]
+    fn loops() {
+        for seed in 0..10 {
+            let mut grid = Grid::new(8, 6).wrapping(true, false);
+            crate::gen::recursive_backtracker::on(&mut grid, &mut Random::new(seed));
+            grid.braid(1.0, &mut Random::new(seed));
+            let (start, goal) = (Pos::of(2, 2), Pos::of(5, 6));
+            let shortest = Distances::from(&grid, start).path_to(goal).len();
+
+            assert_eq!(dead_end_filling::solve(&grid, start, goal).len(), shortest);
+            assert_eq!(a_star::solve(&grid, start, goal).len(), shortest);
+            let path = tremaux::solve(&grid, start, goal);
+            assert_walkable(&grid, &path, "tremaux");
+            assert_eq!((path[0], *path.last().unwrap()), (start, goal));
+            // Might go around an island forever and give up instead.
+            assert_walkable(&grid, &wall_follower::solve(&grid, start, goal), "wall follower");
+        }
+    }
+
+    #[test]
+    fn other_shapes() {
+        let mut grid = PolarGrid::new(6);
+        crate::gen::wilsons::on(&mut grid, &mut Random::new(1));
+        grid.braid(0.5, &mut Random::new(1));
+        let (start, goal) = (Pos::of(0, 0), Pos::of(5, 3));
+        let shortest = Distances::from(&grid, start).path_to(goal);
+        assert_eq!(dead_end_filling::solve(&grid, start, goal).len(), shortest.len());
+        assert_eq!(run(a_star::AStar::new(&grid, start, goal, |_| 0)).len(), shortest.len());
+        assert_walkable(&grid, &tremaux::solve(&grid, start, goal), "tremaux");
+    }
+
+    #[test]
+    fn unreachable() {
+        let mut grid = Grid::new(4, 4);
+        grid.link(Pos::of(0, 0), Pos::of(0, 1));
+        let (start, goal) = (Pos::of(0, 0), Pos::of(3, 3));
+        assert!(wall_follower::solve(&grid, start, goal).is_empty());
+        assert!(dead_end_filling::solve(&grid, start, goal).is_empty());
+        assert!(tremaux::solve(&grid, start, goal).is_empty());
+        assert!(a_star::solve(&grid, start, goal).is_empty());
+    }
+
+    #[test]
+    fn weighted() {
+        // A loop around the middle cell, so there are two ways from the top left to the bottom right.
+        let mut grid = Grid::new(3, 3);
+        let passages = [
+            ((0, 0), (0, 1)), ((0, 1), (0, 2)), ((0, 2), (1, 2)), ((1, 2), (2, 2)),
+            ((0, 0), (1, 0)), ((1, 0), (2, 0)), ((2, 0), (2, 1)), ((2, 1), (2, 2)),
+        ];
+        for ((a_row, a_col), (b_row, b_col)) in passages {
+            grid.link(Pos::of(a_row, a_col), Pos::of(b_row, b_col));
+        }
+        let (start, goal) = (Pos::of(0, 0), Pos::of(2, 2));
+        let (top, bottom) = (Pos::of(0, 1), Pos::of(1, 0));
+        assert_eq!(Distances::from(&grid, start).get(goal), Some(4));
+
+        grid.mut_cell(bottom).set_cost(5);
+        let distances = Distances::from(&grid, start);
+        assert_eq!(distances.path_to(goal), vec![start, top, Pos::of(0, 2), Pos::of(1, 2), goal]);
+        assert_eq!(a_star::solve(&grid, start, goal), distances.path_to(goal));
+        assert_eq!(distances.get(goal), Some(4));
+        assert_eq!(distances.get(bottom), Some(5));
+
+        // Going through the mud is cheaper than the lava.
+        grid.mut_cell(top).set_cost(9);
+        let distances = Distances::from(&grid, start);
+        assert_eq!(distances.path_to(goal), vec![start, bottom, Pos::of(2, 0), Pos::of(2, 1), goal]);
+        assert_eq!(a_star::solve(&grid, start, goal), distances.path_to(goal));
+        assert_eq!(distances.get(goal), Some(8));
+
+        grid.mut_cell(top).set_cost(1);
+        grid.mut_cell(bottom).set_cost(1);
+        assert!(!grid.get_cell(top).is_weighted());
+        assert_eq!(Distances::from(&grid, start).get(goal), Some(4));
+    }
+
+    #[test]
+    fn steps() {
+        let mut grid = Grid::new(8, 6);
+        crate::gen::recursive_backtracker::on(&mut grid, &mut Random::new(2));
+        let (start, goal) = (Pos::of(0, 0), Pos::of(5, 7));
+        let mut solver = tremaux::Tremaux::new(&grid, start, goal);
+        assert_eq!(solver.next(), Some(Step::Visit(start)));
+        assert!(solver.path().is_empty());
+        let steps: Vec<Step> = solver.by_ref().collect();
+        assert_eq!(steps.last(), Some(&Step::Visit(goal)));
+        assert!(!solver.path().is_empty());
+
+        // Everything not on the path gets filled in.
+        let filled = dead_end_filling::DeadEndFilling::new(&grid, start, goal).count();
+        assert_eq!(filled + solver.path().len(), grid.size());
+    }
+}
@@ -0,0 +1,115 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use crate::grid::{Grid, Pos};
+use crate::solve::{run, Solver, Step};
+use crate::topology::Topology;
+
+/// Like Dijkstra's but looks at cells that seem closer to the goal first, so it usually doesn't have to flood the whole maze.
+/// Walking into a cell costs the same as in Dijkstra's, so mud is avoided if there's a cheaper way around.
+/// The heuristic guesses how much it costs to get from a cell to the goal. If it never guesses too high, the path is the cheapest one.
+pub struct AStar<'a> {
+    grid: &'a dyn Topology,
+    goal: Pos,
+    heuristic: Box<dyn Fn(Pos) -> i32 + 'a>,
+    /// Cells waiting to be looked at by (guessed total cost, guessed cost left, index in queued), cheapest first.
+    open: BinaryHeap<Reverse<(i32, i32, usize)>>,
+    queued: Vec<Pos>,
+    costs: Vec<Option<i32>>,
+    parents: Vec<Option<Pos>>,
+    closed: Vec<bool>,
+    found: bool,
+}
+
+impl<'a> AStar<'a> {
+    pub fn new(grid: &'a dyn Topology, start: Pos, goal: Pos, heuristic: impl Fn(Pos) -> i32 + 'a) -> AStar<'a> {
+        let mut solver = AStar {
+            grid,
+            goal,
+            heuristic: Box::new(heuristic),
+            open: BinaryHeap::new(),
+            queued: vec![],
+            costs: vec![None; grid.capacity()],
+            parents: vec![None; grid.capacity()],
+            closed: vec![false; grid.capacity()],
+            found: false,
+        };
+        solver.costs[grid.index(start)] = Some(0);
+        solver.queue(start, 0);
+        solver
+    }
+
+    fn queue(&mut self, pos: Pos, cost: i32) {
+        let guess = (self.heuristic)(pos);
+        self.open.push(Reverse((cost + guess, guess, self.queued.len())));
+        self.queued.push(pos);
+    }
+}
+
+impl Iterator for AStar<'_> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        while let Some(Reverse((_, _, i))) = self.open.pop() {
+            let pos = self.queued[i];
+            let index = self.grid.index(pos);
+            if self.closed[index] {
+                continue;
+            }
+            self.closed[index] = true;
+
+            if pos == self.goal {
+                self.found = true;
+                self.open.clear();
+                return Some(Step::Visit(pos));
+            }
+
+            let distance = self.costs[index].unwrap();
+            for linked in self.grid.links(pos) {
+                let linked_index = self.grid.index(linked);
+                let cost = distance + self.grid.get_cell(linked).cost();
+                if !self.closed[linked_index] && self.costs[linked_index].is_none_or(|old| cost < old) {
+                    self.costs[linked_index] = Some(cost);
+                    self.parents[linked_index] = Some(pos);
+                    self.queue(linked, cost);
+                }
+            }
+            return Some(Step::Visit(pos));
+        }
+        None
+    }
+}
+
+impl Solver for AStar<'_> {
+    fn path(&self) -> Vec<Pos> {
+        if !self.found {
+            return vec![];
+        }
+
+        let mut path = vec![self.goal];
+        while let Some(parent) = self.parents[self.grid.index(*path.last().unwrap())] {
+            path.push(parent);
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// How many steps it would take to get to the goal on a square grid with no walls, going around wrapping edges if that's shorter.
+/// Never too high since every step costs at least 1.
+pub fn manhattan(grid: &Grid, goal: Pos) -> impl Fn(Pos) -> i32 + '_ {
+    move |pos| {
+        let mut rows = (pos.row - goal.row).abs();
+        let mut cols = (pos.col - goal.col).abs();
+        if grid.wrap_north_south {
+            rows = rows.min(grid.rows - rows);
+        }
+        if grid.wrap_east_west {
+            cols = cols.min(grid.cols - cols);
+        }
+        rows + cols
+    }
+}
+
+pub fn solve(grid: &Grid, start: Pos, goal: Pos) -> Vec<Pos> {
+    run(AStar::new(grid, start, goal, manhattan(grid, goal)))
+}
@@ -0,0 +1,98 @@
+use std::collections::VecDeque;
+use crate::grid::Pos;
+use crate::solve::{run, Solver, Step};
+use crate::topology::Topology;
+
+/// Looks at the whole maze from above and fills in dead ends until only the ways from the start to the goal are left.
+/// Filling a dead end can make the cell before it a new dead end, so whole branches get filled back to where they join.
+/// It never walks through the maze so every step is a dead end.
+pub struct DeadEndFilling<'a> {
+    grid: &'a dyn Topology,
+    start: Pos,
+    goal: Pos,
+    /// How many passages out of each cell don't lead into a filled cell.
+    open: Vec<usize>,
+    filled: Vec<bool>,
+    dead_ends: Vec<Pos>,
+}
+
+impl<'a> DeadEndFilling<'a> {
+    pub fn new(grid: &'a dyn Topology, start: Pos, goal: Pos) -> DeadEndFilling<'a> {
+        let mut open = vec![0; grid.capacity()];
+        let mut dead_ends = vec![];
+        for pos in grid.positions() {
+            open[grid.index(pos)] = grid.links(pos).len();
+            if grid.links(pos).len() == 1 && pos != start && pos != goal {
+                dead_ends.push(pos);
+            }
+        }
+
+        DeadEndFilling {
+            grid,
+            start,
+            goal,
+            open,
+            filled: vec![false; grid.capacity()],
+            dead_ends,
+        }
+    }
+}
+
+impl Iterator for DeadEndFilling<'_> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        let pos = self.dead_ends.pop()?;
+        self.filled[self.grid.index(pos)] = true;
+        for linked in self.grid.links(pos) {
+            let index = self.grid.index(linked);
+            if self.filled[index] {
+                continue;
+            }
+
+            self.open[index] -= 1;
+            if self.open[index] == 1 && linked != self.start && linked != self.goal {
+                self.dead_ends.push(linked);
+            }
+        }
+        Some(Step::DeadEnd(pos))
+    }
+}
+
+impl Solver for DeadEndFilling<'_> {
+    /// The shortest way through what's left. If the maze is perfect, there's only one.
+    fn path(&self) -> Vec<Pos> {
+        if !self.dead_ends.is_empty() {
+            return vec![];
+        }
+
+        let mut parents = vec![None; self.grid.capacity()];
+        let mut reached = vec![false; self.grid.capacity()];
+        reached[self.grid.index(self.start)] = true;
+        let mut frontier = VecDeque::from([self.start]);
+        while let Some(pos) = frontier.pop_front() {
+            if pos == self.goal {
+                let mut path = vec![pos];
+                while let Some(parent) = parents[self.grid.index(*path.last().unwrap())] {
+                    path.push(parent);
+                }
+                path.reverse();
+                return path;
+            }
+
+            for linked in self.grid.links(pos) {
+                let index = self.grid.index(linked);
+                if !reached[index] && !self.filled[index] {
+                    reached[index] = true;
+                    parents[index] = Some(pos);
+                    frontier.push_back(linked);
+                }
+            }
+        }
+        vec![]
+    }
+}
+
+pub fn solve(grid: &dyn Topology, start: Pos, goal: Pos) -> Vec<Pos> {
+    run(DeadEndFilling::new(grid, start, goal))
+}
@@ -1 +1,102 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use crate::grid::Pos;
+use crate::topology::Topology;
 
+/// How much it costs to walk from the root to every cell in the grid, going the cheapest way.
+/// Walking into a cell costs 1 unless it's been given a higher cost, so in an unweighted grid this is the number of steps.
+pub struct Distances<'a> {
+    grid: &'a dyn Topology,
+    root: Pos,
+    distances: Vec<Option<i32>>,
+    parents: Vec<Option<Pos>>,
+}
+
+impl<'a> Distances<'a> {
+    pub fn from(grid: &'a dyn Topology, root: Pos) -> Distances<'a> {
+        let mut distances = Distances {
+            grid,
+            root,
+            distances: vec![None; grid.capacity()],
+            parents: vec![None; grid.capacity()],
+        };
+        distances.distances[grid.index(root)] = Some(0);
+
+        // Cells waiting to be looked at by (cost so far, index in queued), cheapest first.
+        // Ties come out in the order they went in, so an unweighted grid is flooded breadth first.
+        let mut open = BinaryHeap::from([Reverse((0, 0))]);
+        let mut queued = vec![root];
+        let mut closed = vec![false; grid.capacity()];
+        while let Some(Reverse((distance, i))) = open.pop() {
+            let pos = queued[i];
+            let index = grid.index(pos);
+            if closed[index] {
+                continue;
+            }
+            closed[index] = true;
+
+            for linked in grid.links(pos) {
+                let linked_index = grid.index(linked);
+                let cost = distance + grid.get_cell(linked).cost();
+                if distances.distances[linked_index].is_none_or(|old| cost < old) {
+                    distances.distances[linked_index] = Some(cost);
+                    distances.parents[linked_index] = Some(pos);
+                    open.push(Reverse((cost, queued.len())));
+                    queued.push(linked);
+                }
+            }
+        }
+
+        distances
+    }
+
+    pub fn root(&self) -> Pos {
+        self.root
+    }
+
+    /// The cost of the cheapest way from the root to the pos or None if it can't be reached.
+    pub fn get(&self, pos: Pos) -> Option<i32> {
+        self.distances[self.grid.index(pos)]
+    }
+
+    /// The cells to walk through to get from the root to the goal the cheapest way, including both ends.
+    /// Empty if the goal can't be reached.
+    pub fn path_to(&self, goal: Pos) -> Vec<Pos> {
+        if self.get(goal).is_none() {
+            return vec![];
+        }
+
+        let mut path = vec![goal];
+        let mut current = goal;
+        while let Some(parent) = self.parents[self.grid.index(current)] {
+            path.push(parent);
+            current = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// The cell that costs the most to get to from the root and how much it costs.
+    pub fn max(&self) -> (Pos, i32) {
+        let mut furthest = (self.root, 0);
+        for pos in self.grid.positions() {
+            if let Some(distance) = self.get(pos) {
+                if distance > furthest.1 {
+                    furthest = (pos, distance);
+                }
+            }
+        }
+        furthest
+    }
+}
+
+/// Finds the two cells that are furthest apart and returns the path between them.
+/// First finds the cell furthest from an arbitrary cell, which must be one end of the longest path,
+/// then the cell furthest from that is the other end. In a weighted or braided maze it's only a good guess.
+pub fn longest_path(grid: &dyn Topology) -> Vec<Pos> {
+    let first = *grid.positions().first().expect("Grid has no cells.");
+    let (start, _) = Distances::from(grid, first).max();
+    let distances = Distances::from(grid, start);
+    let (goal, _) = distances.max();
+    distances.path_to(goal)
+}
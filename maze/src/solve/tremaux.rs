@@ -0,0 +1,104 @@
+use std::collections::{HashMap, VecDeque};
+use crate::grid::Pos;
+use crate::solve::{run, Solver, Step, Walk};
+use crate::topology::Topology;
+
+/// Walks through the maze marking each passage as it goes, like chalk on the floor.
+/// Prefers passages it hasn't been down, turns back when a new passage leads somewhere it's already been,
+/// and never goes down a passage a third time. Works on any maze, even with loops.
+pub struct Tremaux<'a> {
+    grid: &'a dyn Topology,
+    goal: Pos,
+    walk: Walk,
+    /// How many times each passage has been walked, by the indices of the cells at either end.
+    marks: HashMap<(usize, usize), u8>,
+    visited: Vec<bool>,
+    /// The last cell it was in, which isn't on the walk any more after turning back.
+    came_from: Option<Pos>,
+    /// It just took a new passage to a cell it had already been to.
+    turn_back: bool,
+    steps: VecDeque<Step>,
+    done: bool,
+    found: bool,
+}
+
+impl<'a> Tremaux<'a> {
+    pub fn new(grid: &'a dyn Topology, start: Pos, goal: Pos) -> Tremaux<'a> {
+        let mut visited = vec![false; grid.capacity()];
+        visited[grid.index(start)] = true;
+        Tremaux {
+            grid,
+            goal,
+            walk: Walk::new(grid, start),
+            marks: HashMap::new(),
+            visited,
+            came_from: None,
+            turn_back: false,
+            steps: VecDeque::from([Step::Visit(start)]),
+            done: false,
+            found: false,
+        }
+    }
+
+    fn passage(&self, a: Pos, b: Pos) -> (usize, usize) {
+        let (a, b) = (self.grid.index(a), self.grid.index(b));
+        (a.min(b), a.max(b))
+    }
+
+    fn marks(&self, a: Pos, b: Pos) -> u8 {
+        self.marks.get(&self.passage(a, b)).copied().unwrap_or(0)
+    }
+
+    fn advance(&mut self) {
+        let pos = self.walk.current();
+        if pos == self.goal {
+            self.done = true;
+            self.found = true;
+            return;
+        }
+
+        let links = self.grid.links(pos);
+        let entrance = self.came_from.filter(|from| self.marks(pos, *from) == 1);
+        let next = if self.turn_back {
+            self.came_from
+        } else {
+            links.iter().copied().find(|other| self.marks(pos, *other) == 0)
+                .or(entrance)
+                .or_else(|| links.iter().copied().find(|other| self.marks(pos, *other) == 1))
+        };
+        let Some(next) = next else {
+            self.done = true;
+            return;
+        };
+
+        let passage = self.passage(pos, next);
+        let marks = self.marks.entry(passage).or_insert(0);
+        *marks += 1;
+        let index = self.grid.index(next);
+        self.turn_back = self.visited[index] && *marks == 1;
+        self.visited[index] = true;
+        self.came_from = Some(pos);
+        self.walk.move_to(self.grid, next, &mut self.steps);
+    }
+}
+
+impl Iterator for Tremaux<'_> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        while self.steps.is_empty() && !self.done {
+            self.advance();
+        }
+        self.steps.pop_front()
+    }
+}
+
+impl Solver for Tremaux<'_> {
+    fn path(&self) -> Vec<Pos> {
+        if self.found { self.walk.cells.clone() } else { vec![] }
+    }
+}
+
+pub fn solve(grid: &dyn Topology, start: Pos, goal: Pos) -> Vec<Pos> {
+    run(Tremaux::new(grid, start, goal))
+}
@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+use crate::grid::{Grid, Pos};
+use crate::solve::{run, Solver, Step, Walk};
+use crate::topology::Topology;
+
+/// Keeps its right hand on the wall and walks until it reaches the goal.
+/// Always works in a perfect maze but can go around in circles forever if there are loops, so it gives up
+/// when it gets back to somewhere it's already been facing the same way.
+/// Only works on square grids since it needs to know which way is right.
+pub struct WallFollower<'a> {
+    grid: &'a Grid,
+    goal: Pos,
+    /// Index into the clockwise directions of the way it's facing.
+    facing: usize,
+    walk: Walk,
+    /// Every cell and the way it was facing there, to notice when it's going in circles.
+    seen: Vec<bool>,
+    steps: VecDeque<Step>,
+    done: bool,
+    found: bool,
+}
+
+impl<'a> WallFollower<'a> {
+    pub fn new(grid: &'a Grid, start: Pos, goal: Pos) -> WallFollower<'a> {
+        WallFollower {
+            grid,
+            goal,
+            facing: 0,
+            walk: Walk::new(grid, start),
+            seen: vec![false; grid.capacity() * 4],
+            steps: VecDeque::from([Step::Visit(start)]),
+            done: false,
+            found: false,
+        }
+    }
+
+    fn towards(&self, pos: Pos, facing: usize) -> Pos {
+        match facing {
+            0 => self.grid.north(pos),
+            1 => self.grid.east(pos),
+            2 => self.grid.south(pos),
+            _ => self.grid.west(pos),
+        }
+    }
+
+    fn advance(&mut self) {
+        let pos = self.walk.current();
+        if pos == self.goal {
+            self.done = true;
+            self.found = true;
+            return;
+        }
+
+        let state = (self.grid.index(pos) * 4) + self.facing;
+        if self.seen[state] {
+            self.done = true;
+            return;
+        }
+        self.seen[state] = true;
+
+        // Right, straight on, left, then back the way it came.
+        let turn = [1, 0, 3, 2].into_iter().find(|turn| {
+            let facing = (self.facing + turn) % 4;
+            self.grid.is_linked(pos, self.towards(pos, facing))
+        });
+        match turn {
+            Some(turn) => {
+                self.facing = (self.facing + turn) % 4;
+                let next = self.towards(pos, self.facing);
+                self.walk.move_to(self.grid, next, &mut self.steps);
+            }
+            None => self.done = true,
+        }
+    }
+}
+
+impl Iterator for WallFollower<'_> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        while self.steps.is_empty() && !self.done {
+            self.advance();
+        }
+        self.steps.pop_front()
+    }
+}
+
+impl Solver for WallFollower<'_> {
+    fn path(&self) -> Vec<Pos> {
+        if self.found { self.walk.cells.clone() } else { vec![] }
+    }
+}
+
+pub fn solve(grid: &Grid, start: Pos, goal: Pos) -> Vec<Pos> {
+    run(WallFollower::new(grid, start, goal))
+}
@@ -0,0 +1,207 @@
+use crate::grid::{Cell, GridError, Links, Pos};
+use crate::Random;
+
+/// Which cells exist in a grid and which ones are next to each other.
+/// Generators and solvers that only care about neighbours and links work on any shape of grid through this.
+pub trait Topology {
+    /// How many slots are needed to store something for every cell. Every index is below this.
+    fn capacity(&self) -> usize;
+
+    /// A unique number for every pos in the grid, for storing things about cells in a vec.
+    fn index(&self, pos: Pos) -> usize;
+
+    fn has(&self, pos: Pos) -> bool;
+
+    /// Every cell in the grid.
+    fn positions(&self) -> Vec<Pos>;
+
+    /// The cells next to a pos that could be linked to it, whether or not they are.
+    fn neighbours(&self, pos: Pos) -> Vec<Pos>;
+
+    /// Every cell that a pos could ever be linked to, always in the same order.
+    /// Each one is a direction with a bit in the cell, so there can't be more than Cell::MAX_DIRECTIONS.
+    fn directions(&self, pos: Pos) -> Vec<Pos> {
+        self.neighbours(pos)
+    }
+
+    fn get_cell(&self, pos: Pos) -> &Cell;
+
+    fn mut_cell(&mut self, pos: Pos) -> &mut Cell;
+
+    fn links(&self, pos: Pos) -> Links {
+        let cell = self.get_cell(pos);
+        self.directions(pos)
+            .into_iter()
+            .enumerate()
+            .filter(|(direction, _)| cell.is_linked(*direction))
+            .map(|(_, other)| other)
+            .collect()
+    }
+
+    /// Connect two cells with a passage in both directions.
+    fn link(&mut self, a: Pos, b: Pos) {
+        set_link(self, a, b, true);
+    }
+
+    fn unlink(&mut self, a: Pos, b: Pos) {
+        set_link(self, a, b, false);
+    }
+
+    fn is_linked(&self, a: Pos, b: Pos) -> bool {
+        self.has(a) && direction(self, a, b).is_some_and(|direction| self.get_cell(a).is_linked(direction))
+    }
+
+    fn size(&self) -> usize {
+        self.positions().len()
+    }
+
+    fn random_pos(&self, rng: &mut Random) -> Pos {
+        let positions = self.positions();
+        positions[rng.rand_below(positions.len())]
+    }
+
+    /// Checks every link goes both ways between two cells in the grid.
+    fn validate(&self) -> Result<(), Vec<GridError>> {
+        link_errors(self, self.positions())
+    }
+
+    /// Groups of cells that can all reach each other, so more than one means some are closed off.
+    fn connected_components(&self) -> Vec<Vec<Pos>> {
+        let mut seen = vec![false; self.capacity()];
+        let mut components = vec![];
+        for start in self.positions() {
+            if seen[self.index(start)] {
+                continue;
+            }
+
+            seen[self.index(start)] = true;
+            let mut component = vec![start];
+            let mut i = 0;
+            while i < component.len() {
+                for linked in self.links(component[i]) {
+                    if self.has(linked) && !seen[self.index(linked)] {
+                        seen[self.index(linked)] = true;
+                        component.push(linked);
+                    }
+                }
+                i += 1;
+            }
+            components.push(component);
+        }
+        components
+    }
+
+    /// Every cell a maze started at pos could spread to, going from neighbour to neighbour whether or not they're linked.
+    /// Only less than every cell when a mask splits the grid into separate areas.
+    fn reachable(&self, start: Pos) -> Vec<Pos> {
+        let mut seen = vec![false; self.capacity()];
+        seen[self.index(start)] = true;
+        let mut area = vec![start];
+        let mut i = 0;
+        while i < area.len() {
+            for next in self.neighbours(area[i]) {
+                if !seen[self.index(next)] {
+                    seen[self.index(next)] = true;
+                    area.push(next);
+                }
+            }
+            i += 1;
+        }
+        area
+    }
+
+    /// There's exactly one way between any two cells, with no loops or closed off areas.
+    /// A connected grid is a tree if it has one less passage than cells.
+    fn is_perfect(&self) -> bool {
+        let positions = self.positions();
+        let passages = positions.iter().map(|pos| self.links(*pos).len()).sum::<usize>() / 2;
+        self.validate().is_ok() && passages + 1 == positions.len() && self.connected_components().len() == 1
+    }
+
+    /// Cells with only one passage out.
+    fn dead_ends(&self) -> Vec<Pos> {
+        self.positions().into_iter().filter(|pos| self.get_cell(*pos).link_count() == 1).collect()
+    }
+
+    /// Removes dead ends by linking them to another neighbour, which makes loops.
+    /// Each dead end has a p chance (between 0 and 1) of being removed.
+    /// Linking two dead ends together gets rid of both so those neighbours are preferred.
+    fn braid(&mut self, p: f64, rng: &mut Random) {
+        if p <= 0.0 {
+            return;
+        }
+
+        let mut dead_ends = self.dead_ends();
+        rng.shuffle(&mut dead_ends);
+        for pos in dead_ends {
+            // It might have been linked to by an earlier dead end.
+            if self.links(pos).len() != 1 || !rng.chance(p) {
+                continue;
+            }
+
+            let unlinked: Vec<Pos> = self.neighbours(pos).into_iter().filter(|check| !self.is_linked(pos, *check)).collect();
+            let best: Vec<Pos> = unlinked.iter().copied().filter(|check| self.links(*check).len() == 1).collect();
+            let choices = if best.is_empty() { unlinked } else { best };
+            if choices.is_empty() {
+                continue;
+            }
+            let other = choices[rng.rand_below(choices.len())];
+            self.link(pos, other);
+        }
+    }
+
+    /// Gives each cell a p chance (between 0 and 1) of costing more to walk into, like patches of mud.
+    /// Only matters if the maze has loops, otherwise there's no other way around.
+    fn add_weights(&mut self, p: f64, cost: u16, rng: &mut Random) {
+        if p <= 0.0 {
+            return;
+        }
+
+        for pos in self.positions() {
+            if rng.chance(p) {
+                self.mut_cell(pos).set_cost(cost);
+            }
+        }
+    }
+}
+
+/// Every problem with the links out of the cells at each pos.
+pub(crate) fn link_errors<T: Topology + ?Sized>(grid: &T, positions: impl IntoIterator<Item = Pos>) -> Result<(), Vec<GridError>> {
+    let mut errors = vec![];
+    for pos in positions {
+        for linked in grid.links(pos) {
+            if !grid.has(pos) || !grid.has(linked) {
+                errors.push(GridError::NotInGrid { from: pos, to: linked });
+            } else if !grid.is_linked(linked, pos) {
+                errors.push(GridError::OneWay { from: pos, to: linked });
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Which bit of a's cell links it to b, if b is one of its directions.
+fn direction<T: Topology + ?Sized>(grid: &T, a: Pos, b: Pos) -> Option<usize> {
+    grid.directions(a).into_iter().position(|pos| pos == b)
+}
+
+/// Sets the bits for a passage between two cells on both sides.
+/// For shapes that override link but still store the normal way underneath.
+pub fn set_link<T: Topology + ?Sized>(grid: &mut T, a: Pos, b: Pos, linked: bool) {
+    let (Some(forward), Some(back)) = (direction(grid, a, b), direction(grid, b, a)) else {
+        panic!("Can't link {:?} to {:?} because they aren't next to each other", a, b);
+    };
+    for (pos, direction) in [(a, forward), (b, back)] {
+        let cell = grid.mut_cell(pos);
+        if linked {
+            cell.link(direction);
+        } else {
+            cell.unlink(direction);
+        }
+    }
+}
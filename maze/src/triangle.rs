@@ -0,0 +1,81 @@
+use crate::grid::{Cell, Pos};
+use crate::topology::Topology;
+
+/// A grid of triangles that alternate between pointing up and down along each row.
+/// Every cell has neighbours to the east and west, and one more across its flat base.
+#[derive(Clone)]
+pub struct TriangleGrid {
+    pub rows: i32,
+    pub cols: i32,
+    pub cells: Vec<Cell>,
+}
+
+impl TriangleGrid {
+    pub fn new(rows: i32, cols: i32) -> TriangleGrid {
+        TriangleGrid {
+            rows,
+            cols,
+            cells: vec![Cell::default(); (rows * cols) as usize],
+        }
+    }
+
+    /// Upright triangles have their point at the top and their base on the south side.
+    pub fn is_upright(&self, pos: Pos) -> bool {
+        (pos.row + pos.col) % 2 == 0
+    }
+
+    pub fn east(&self, pos: Pos) -> Pos {
+        Pos::of(pos.row, pos.col + 1)
+    }
+
+    pub fn west(&self, pos: Pos) -> Pos {
+        Pos::of(pos.row, pos.col - 1)
+    }
+
+    /// The cell on the other side of the flat edge, which is below upright cells and above the others.
+    pub fn across_base(&self, pos: Pos) -> Pos {
+        if self.is_upright(pos) {
+            Pos::of(pos.row + 1, pos.col)
+        } else {
+            Pos::of(pos.row - 1, pos.col)
+        }
+    }
+}
+
+impl Topology for TriangleGrid {
+    fn capacity(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn index(&self, pos: Pos) -> usize {
+        if !self.has(pos) {
+            panic!("Invalid pos {:?}", pos);
+        }
+
+        ((pos.row * self.cols) + pos.col) as usize
+    }
+
+    fn has(&self, pos: Pos) -> bool {
+        pos.row >= 0 && pos.row < self.rows && pos.col >= 0 && pos.col < self.cols
+    }
+
+    fn positions(&self) -> Vec<Pos> {
+        (0..self.rows).flat_map(|row| (0..self.cols).map(move |col| Pos::of(row, col))).collect()
+    }
+
+    fn neighbours(&self, pos: Pos) -> Vec<Pos> {
+        [self.west(pos), self.east(pos), self.across_base(pos)]
+            .into_iter()
+            .filter(|check| self.has(*check))
+            .collect()
+    }
+
+    fn get_cell(&self, pos: Pos) -> &Cell {
+        &self.cells[self.index(pos)]
+    }
+
+    fn mut_cell(&mut self, pos: Pos) -> &mut Cell {
+        let index = self.index(pos);
+        &mut self.cells[index]
+    }
+}
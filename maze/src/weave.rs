@@ -0,0 +1,180 @@
+use crate::grid::{Cell, Pos};
+use crate::topology::{set_link, Topology};
+
+/// A square grid where passages can tunnel under straight corridors to the cell on the other side.
+/// Cells on level 0 are the normal ones. A tunnel adds a cell on level 1 under the corridor it passes,
+/// at the same row and col, which links the cells at either end of the tunnel.
+#[derive(Clone)]
+pub struct WeaveGrid {
+    pub rows: i32,
+    pub cols: i32,
+    /// Every over cell followed by a slot for the under cell below each one.
+    pub cells: Vec<Cell>,
+    tunnels: Vec<bool>,
+}
+
+impl WeaveGrid {
+    pub fn new(rows: i32, cols: i32) -> WeaveGrid {
+        WeaveGrid {
+            rows,
+            cols,
+            cells: vec![Cell::default(); (2 * rows * cols) as usize],
+            tunnels: vec![false; (rows * cols) as usize],
+        }
+    }
+
+    pub fn north(&self, pos: Pos) -> Pos {
+        Pos::of(pos.row - 1, pos.col)
+    }
+
+    pub fn south(&self, pos: Pos) -> Pos {
+        Pos::of(pos.row + 1, pos.col)
+    }
+
+    pub fn east(&self, pos: Pos) -> Pos {
+        Pos::of(pos.row, pos.col + 1)
+    }
+
+    pub fn west(&self, pos: Pos) -> Pos {
+        Pos::of(pos.row, pos.col - 1)
+    }
+
+    /// The under cell below a pos, whether or not there's a tunnel there.
+    pub fn under(&self, pos: Pos) -> Pos {
+        Pos::at(1, pos.row, pos.col)
+    }
+
+    /// Does a tunnel pass under this cell.
+    pub fn has_tunnel(&self, pos: Pos) -> bool {
+        self.in_bounds(pos) && self.tunnels[self.over_index(pos)]
+    }
+
+    /// Linked east and west but not north or south.
+    pub fn is_horizontal_corridor(&self, pos: Pos) -> bool {
+        self.is_linked(pos, self.east(pos)) && self.is_linked(pos, self.west(pos))
+            && !self.is_linked(pos, self.north(pos)) && !self.is_linked(pos, self.south(pos))
+    }
+
+    /// Linked north and south but not east or west.
+    pub fn is_vertical_corridor(&self, pos: Pos) -> bool {
+        self.is_linked(pos, self.north(pos)) && self.is_linked(pos, self.south(pos))
+            && !self.is_linked(pos, self.east(pos)) && !self.is_linked(pos, self.west(pos))
+    }
+
+    /// Would linking the over cells a and b join the side of a straight corridor.
+    /// That's not allowed so anything tunnelling under the corridor stays valid.
+    fn joins_corridor_side(&self, a: Pos, b: Pos) -> bool {
+        let vertical = a.col == b.col;
+        [a, b].into_iter().any(|pos| {
+            if vertical { self.is_horizontal_corridor(pos) } else { self.is_vertical_corridor(pos) }
+        })
+    }
+
+    fn in_bounds(&self, pos: Pos) -> bool {
+        pos.row >= 0 && pos.row < self.rows && pos.col >= 0 && pos.col < self.cols
+    }
+
+    fn over_index(&self, pos: Pos) -> usize {
+        ((pos.row * self.cols) + pos.col) as usize
+    }
+}
+
+impl Topology for WeaveGrid {
+    fn capacity(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn index(&self, pos: Pos) -> usize {
+        if !self.in_bounds(pos) || pos.level < 0 || pos.level > 1 {
+            panic!("Invalid pos {:?}", pos);
+        }
+
+        (pos.level * self.rows * self.cols) as usize + self.over_index(pos)
+    }
+
+    fn has(&self, pos: Pos) -> bool {
+        match pos.level {
+            0 => self.in_bounds(pos),
+            1 => self.has_tunnel(pos),
+            _ => false,
+        }
+    }
+
+    fn positions(&self) -> Vec<Pos> {
+        let mut positions = vec![];
+        for level in 0..2 {
+            for row in 0..self.rows {
+                for col in 0..self.cols {
+                    let pos = Pos::at(level, row, col);
+                    if self.has(pos) {
+                        positions.push(pos);
+                    }
+                }
+            }
+        }
+        positions
+    }
+
+    /// The cells next to an over cell, plus the cells on the other side of any straight corridor next to it.
+    /// Under cells are only made by linking so they never get new neighbours.
+    fn neighbours(&self, pos: Pos) -> Vec<Pos> {
+        if pos.level != 0 {
+            return vec![];
+        }
+
+        let directions: [fn(&WeaveGrid, Pos) -> Pos; 4] = [WeaveGrid::north, WeaveGrid::south, WeaveGrid::east, WeaveGrid::west];
+        let mut neighbours = vec![];
+        for direction in directions {
+            let next = direction(self, pos);
+            if !self.in_bounds(next) {
+                continue;
+            }
+
+            if !self.joins_corridor_side(pos, next) {
+                neighbours.push(next);
+            }
+
+            let across = direction(self, next);
+            let crossing = if pos.row == next.row { self.is_vertical_corridor(next) } else { self.is_horizontal_corridor(next) };
+            if crossing && !self.has_tunnel(next) && self.in_bounds(across) {
+                neighbours.push(across);
+            }
+        }
+        neighbours
+    }
+
+    /// Over cells link to the over cells next to them or the under cells at the end of a tunnel.
+    /// Under cells link to the over cells at either end.
+    fn directions(&self, pos: Pos) -> Vec<Pos> {
+        let adjacent = [self.north(pos), self.south(pos), self.east(pos), self.west(pos)];
+        if pos.level != 0 {
+            return adjacent.to_vec();
+        }
+        adjacent.into_iter().chain(adjacent.map(|next| self.under(next))).collect()
+    }
+
+    fn get_cell(&self, pos: Pos) -> &Cell {
+        &self.cells[self.index(pos)]
+    }
+
+    fn mut_cell(&mut self, pos: Pos) -> &mut Cell {
+        let index = self.index(pos);
+        &mut self.cells[index]
+    }
+
+    /// Linking two cells that aren't next to each other digs a tunnel under the one between them.
+    fn link(&mut self, a: Pos, b: Pos) {
+        let distance = (a.row - b.row).abs() + (a.col - b.col).abs();
+        if a.level == 0 && b.level == 0 && distance == 2 {
+            let middle = Pos::of((a.row + b.row) / 2, (a.col + b.col) / 2);
+            let index = self.over_index(middle);
+            self.tunnels[index] = true;
+            let under = self.under(middle);
+            self.link(a, under);
+            self.link(under, b);
+            return;
+        }
+
+        set_link(self, a, b, true);
+    }
+}
@@ -43,8 +43,11 @@ fn inner_render2d<R: RenderStrategy>(world: &World, canvas: &mut R) {
             }
         }
 
-        // Draw walls
+        // Draw walls, leaving out the seams between tiles of a big maze.
         for wall in region.walls() {
+            if wall.portal().is_some_and(|portal| portal.is_seamless()) {
+                continue;
+            }
             let contains_player = world.player().entity.region == wall.region;
             draw_wall_2d(canvas, wall, contains_player);
         }
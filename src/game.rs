@@ -1,12 +1,21 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::level::load_level;
 use crate::world::World;
-use crate::world_gen::random_maze_world;
+use crate::world_gen::{example_preset, random_maze_world, Carving, EndlessMaze, MazeChain, MazeSettings, Scene, Shape, Streaming};
 
 use crate::println;
 
+#[cfg(not(target_arch = "wasm32"))]
+const SAVED_LEVEL_PATH: &str = "saved_level.txt";
+
 pub(crate) struct GameState {
     pub(crate) world: World,
+    pub(crate) settings: MazeSettings,
+    /// Set while the maze is being generated in front of you.
+    carving: Option<Carving>,
+    /// Set if the maze keeps going as you walk.
+    streaming: Option<Streaming>,
     seconds_counter: f64,
     pub(crate) render_frame_counter: i32,
     idle_frame_counter: i32,
@@ -18,11 +27,14 @@ pub(crate) struct GameState {
 }
 
 impl GameState {
-    pub(crate) fn new() -> GameState {
-        let world = random_maze_world();
+    pub(crate) fn new(settings: MazeSettings) -> GameState {
+        let (world, streaming) = new_world(&settings);
 
         GameState {
             world,
+            settings,
+            carving: None,
+            streaming,
             seconds_counter: 0.0,
             render_frame_counter: 0,
             idle_frame_counter: 0,
@@ -60,21 +72,117 @@ impl GameState {
             self.idle_frame_counter = 0;
         }
 
+        if let Some(carving) = &mut self.carving {
+            if carving.update(&mut self.world, duration) {
+                *self.world.player_mut().needs_render_update.write().unwrap() = true;
+            }
+            if carving.is_done() {
+                self.carving = None;
+            }
+        }
+
         self.world.update(duration, &self.keys, self.delta_mouse as i32);
         self.delta_mouse = 0.0;
 
+        if let Some(streaming) = &mut self.streaming {
+            if streaming.update(&mut self.world) {
+                *self.world.player_mut().needs_render_update.write().unwrap() = true;
+            }
+        }
+
         // If you didn't move or turn and nothing in the world changed, don't bother redrawing the screen.
         let changed = *self.world.player().needs_render_update.read().unwrap();
         changed
     }
 
+    /// Starts the current scene again. Mazes are replaced by a new one made by the next generation algorithm.
     pub fn reset_world(&mut self) {
+        if self.settings.scene == Scene::Maze {
+            self.next_maze();
+        }
+        let (world, streaming) = new_world(&self.settings);
+        self.replace_world(world, self.settings.scene == Scene::Maze);
+        self.streaming = streaming;
+    }
+
+    /// Switches to the next scene, skipping the level if there isn't one loaded.
+    pub fn next_scene(&mut self) {
+        let mut scene = self.settings.scene.next();
+        if scene == Scene::Level && self.settings.level.is_none() {
+            scene = scene.next();
+        }
+        self.settings.scene = scene;
+        println!("Switched to the {} scene.", scene.name());
+        let (world, streaming) = new_world(&self.settings);
+        self.replace_world(world, false);
+        self.streaming = streaming;
+    }
+
+    /// Like reset_world but switches to the top down view and carves out the new maze one step at a time.
+    pub fn carve_world(&mut self) {
+        self.settings.scene = Scene::Maze;
+        self.next_maze();
+        match Carving::start(&self.settings) {
+            Some((world, carving)) => {
+                self.replace_world(world, true);
+                self.carving = Some(carving);
+                self.world.player_mut().first_person_rendering = false;
+            }
+            None => {
+                println!("Can't carve a {:?} maze one step at a time.", self.settings.shape);
+                let (world, streaming) = new_world(&self.settings);
+                self.replace_world(world, true);
+                self.streaming = streaming;
+            }
+        }
+    }
+
+    fn next_maze(&mut self) {
+        self.settings.generator = self.settings.generator.next();
+        self.settings.layout = None;
+        self.settings.seed = maze::random_seed();
+    }
+
+    /// Can keep the player where they were so they can see the new maze appear around them.
+    /// Other scenes have their own start that might not be anywhere near the old one.
+    /// The player is moved into whichever region they're standing in, since a maze split into tiles starts them in the first one.
+    /// If none of the new world is where they were, like the far end of an endless maze, they go to the start instead.
+    fn replace_world(&mut self, world: World, keep_player: bool) {
         let player_pos = self.world.player().entity.pos;
         let player_facing = self.world.player().look_direction;
-        self.world = random_maze_world();
+        self.world = world;
+        self.carving = None;
+        self.streaming = None;
         *self.world.player_mut().needs_render_update.write().unwrap() = true;
-        self.world.player_mut().entity.pos = player_pos;
-        self.world.player_mut().look_direction = player_facing;
+        if !keep_player {
+            return;
+        }
+
+        let start = self.world.player().entity.region;
+        let region = if self.world.get_region(start).contains(player_pos) {
+            Some(start)
+        } else {
+            self.world.regions().find(|region| region.contains(player_pos)).map(|region| region.id)
+        };
+        if let Some(region) = region {
+            let player = self.world.player_mut();
+            player.entity.region = region;
+            player.entity.pos = player_pos;
+            player.look_direction = player_facing;
+        }
+    }
+
+    /// Writes the world as a level file that can be loaded again with --level.
+    /// There's no file system on the web so it's printed to the console instead.
+    pub fn save_level(&self) {
+        let text = self.world.to_level();
+        #[cfg(not(target_arch = "wasm32"))]
+        match std::fs::write(SAVED_LEVEL_PATH, &text) {
+            Ok(_) => println!("Saved level to {}", SAVED_LEVEL_PATH),
+            Err(e) => println!("Failed to save level to {}: {}", SAVED_LEVEL_PATH, e),
+        }
+        #[cfg(target_arch = "wasm32")]
+        println!("{}", text);
     }
 
     pub fn toggle_camera_mode(&mut self) {
@@ -84,6 +192,33 @@ impl GameState {
     }
 }
 
+/// The world for the settings' scene, and whatever keeps adding to it if it's a maze that never ends.
+fn new_world(settings: &MazeSettings) -> (World, Option<Streaming>) {
+    match (settings.scene, &settings.level) {
+        (Scene::Maze, _) => {}
+        (Scene::Portals, _) => return (example_preset(), None),
+        (Scene::Level, Some(text)) => match load_level(text) {
+            Ok(world) => return (world, None),
+            Err(e) => println!("Invalid level, making a maze instead. {}", e),
+        },
+        (Scene::Level, None) => println!("No level loaded, making a maze instead."),
+    }
+    if settings.layout.is_some() {
+        return (random_maze_world(settings), None);
+    }
+    match settings.shape {
+        Shape::Endless => {
+            let (world, endless) = EndlessMaze::start(settings);
+            (world, Some(Streaming::Endless(endless)))
+        }
+        Shape::Chain => {
+            let (world, chain) = MazeChain::start(settings);
+            (world, Some(Streaming::Chain(chain)))
+        }
+        _ => (random_maze_world(settings), None),
+    }
+}
+
 #[derive(Debug)]
 pub struct Keys {
     pub w: bool,
@@ -120,3 +255,25 @@ fn perf_now() -> f64 {
         SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs_f64()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mth::Vector2;
+
+    #[test]
+    fn reset_keeps_region() {
+        let mut settings = MazeSettings::new();
+        settings.size = (25, 15);
+        let mut game = GameState::new(settings);
+        assert_eq!(game.world.regions.len(), 6);
+
+        // The middle of the last tile, far from where the maze starts.
+        let pos = Vector2::of(1200.0, 700.0);
+        game.world.player_mut().entity.pos = pos;
+        game.reset_world();
+        let player = &game.world.player().entity;
+        assert_eq!(player.pos, pos);
+        assert!(game.world.get_region(player.region).contains(pos));
+    }
+}
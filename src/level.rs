@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Write};
+use std::str::SplitWhitespace;
+use maze::Random;
+use crate::material::{Colour, Material};
+use crate::mth::{LineSegment2, Vector2};
+use crate::world::{LightKind, Wall, World};
+use crate::world_gen::MapBuilder;
+
+/// Worlds written as text so levels can be made without touching the code.
+/// Each line is a keyword followed by its values, and anything after a # is a comment.
+///
+/// ```text
+/// region <min x> <min y> <max x> <max y> <material>     starts a new region with that floor
+/// wall <x1> <y1> <x2> <y2> normal <x> <y> <material>   adds a wall to the last region
+/// patch <min x> <min y> <max x> <max y> <material>      a rectangle of the last region's floor made of something else
+/// light <x> <y> colour <r> <g> <b>                     adds a light to the last region
+/// portal <region> <wall> <region> <wall>               joins two walls so walking into either comes out of the other
+/// one_way_portal <region> <wall> <region> <wall>       walking into the first wall comes out of the second
+/// start <region> <x> <y>                               where the player starts
+/// ```
+///
+/// Regions, and the walls in each region, are numbered from 0 in the order they're written.
+/// Portals can refer to walls further down the file.
+/// A material is `colour <r> <g> <b>` with each between 0 and 1,
+/// then any of `ambient`, `diffuse`, `specular` or `shininess` followed by a number to change them from the defaults.
+pub(crate) fn load_level(text: &str) -> Result<World, LevelError> {
+    let mut builder = MapBuilder::new();
+    // How many walls each region has so far.
+    let mut wall_counts: Vec<usize> = vec![];
+    let mut portals = vec![];
+    let mut start = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let content = line.split('#').next().unwrap();
+        let mut tokens = Tokens { words: content.split_whitespace(), line: i + 1 };
+        let Some(keyword) = tokens.words.next() else {
+            continue;
+        };
+
+        match keyword {
+            "region" => {
+                let min = tokens.vector()?;
+                let max = tokens.vector()?;
+                let floor = tokens.material()?;
+                builder.new_region(floor, min, max);
+                wall_counts.push(0);
+            }
+            "wall" => {
+                let region = tokens.current_region(&wall_counts)?;
+                let line = LineSegment2::of(tokens.vector()?, tokens.vector()?);
+                tokens.keyword("normal")?;
+                let normal = tokens.vector()?;
+                let material = tokens.material()?;
+                builder.new_wall(region, line, normal, material);
+                wall_counts[region] += 1;
+            }
+            "patch" => {
+                let region = tokens.current_region(&wall_counts)?;
+                let min = tokens.vector()?;
+                let max = tokens.vector()?;
+                let material = tokens.material()?;
+                builder.new_floor_patch(region, min, max, material);
+            }
+            "light" => {
+                let region = tokens.current_region(&wall_counts)?;
+                let pos = tokens.vector()?;
+                tokens.keyword("colour")?;
+                let intensity = tokens.colour()?;
+                builder.new_light(region, intensity, pos);
+            }
+            "portal" | "one_way_portal" => {
+                let from = (tokens.index()?, tokens.index()?);
+                let to = (tokens.index()?, tokens.index()?);
+                portals.push((tokens.line, keyword == "portal", from, to));
+            }
+            "start" => {
+                if start.is_some() {
+                    return Err(tokens.error("The player can only start in one place"));
+                }
+                start = Some((tokens.line, tokens.index()?, tokens.vector()?));
+            }
+            _ => return Err(tokens.error(&format!("Unknown keyword {}", keyword))),
+        }
+        tokens.end()?;
+    }
+
+    // Done at the end so portals can refer to walls that come after them.
+    for (line, both_ways, from, to) in portals {
+        for (region, wall) in [from, to] {
+            match wall_counts.get(region) {
+                None => return Err(LevelError { line, message: format!("There is no region {}", region) }),
+                Some(count) if wall >= *count => {
+                    return Err(LevelError { line, message: format!("Region {} has no wall {}", region, wall) });
+                }
+                _ => {}
+            }
+        }
+        if from == to {
+            return Err(LevelError { line, message: "A portal can't lead to itself".to_string() });
+        }
+
+        if both_ways {
+            builder.bidirectional_portal(from.0, from.1, to.0, to.1);
+        } else {
+            builder.unidirectional_portal(from.0, from.1, to.0, to.1);
+        }
+    }
+
+    let Some((line, region, pos)) = start else {
+        return Err(LevelError { line: text.lines().count(), message: "Missing start".to_string() });
+    };
+    if region >= wall_counts.len() {
+        return Err(LevelError { line, message: format!("There is no region {}", region) });
+    }
+
+    Ok(World::new(builder.build(), region, pos, Random::new(0)))
+}
+
+impl World {
+    /// Writes the world in the format load_level reads, so whatever's happened to it can be loaded again.
+    /// Walls are renumbered in order of their ids since removing walls, or the random ids of the player's portals, can leave gaps.
+    /// Lights shining through portals aren't saved since they're worked out again when it's loaded.
+    pub(crate) fn to_level(&self) -> String {
+        let mut out = String::new();
+        let mut numbers: Vec<HashMap<usize, usize>> = vec![];
+        for region in self.regions() {
+            let (min, max) = (region.lighting.min, region.lighting.max);
+            writeln!(out, "region {} {} {} {} {}", min.x, min.y, max.x, max.y, material_text(&region.floor_material)).unwrap();
+
+            let walls = sorted_walls(region.walls());
+            numbers.push(walls.iter().enumerate().map(|(i, wall)| (wall.id, i)).collect());
+            for wall in walls {
+                let (line, normal) = (wall.line(), wall.normal());
+                writeln!(out, "wall {} {} {} {} normal {} {} {}", line.a.x, line.a.y, line.b.x, line.b.y, normal.x, normal.y, material_text(wall.material())).unwrap();
+            }
+
+            for patch in &region.floor_patches {
+                writeln!(out, "patch {} {} {} {} {}", patch.min.x, patch.min.y, patch.max.x, patch.max.y, material_text(&patch.material)).unwrap();
+            }
+
+            let mut lights: Vec<_> = region.lights().filter(|light| matches!(light.kind, LightKind::DIRECT())).collect();
+            lights.sort_by_key(|light| light.id);
+            for light in lights {
+                let colour = light.intensity;
+                writeln!(out, "light {} {} colour {} {} {}", light.pos.x, light.pos.y, colour.r, colour.g, colour.b).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+
+        for region in self.regions() {
+            for wall in sorted_walls(region.walls()) {
+                let Some(portal) = wall.portal() else {
+                    continue;
+                };
+                let number = |region: usize, wall: usize| numbers.get(region)?.get(&wall).map(|number| (region, *number));
+                // It might lead to a wall that's gone, like one in a region the maze chain reused, so it's left as a plain wall.
+                let (Some(from), Some(to)) = (number(portal.from_region, portal.from_wall), number(portal.to_region, portal.to_wall)) else {
+                    continue;
+                };
+                let back = self.get_region(portal.to_region).get_wall(portal.to_wall).portal();
+                let both_ways = back.is_some_and(|back| back.to_region == portal.from_region && back.to_wall == portal.from_wall);
+                if !both_ways {
+                    writeln!(out, "one_way_portal {} {} {} {}", from.0, from.1, to.0, to.1).unwrap();
+                } else if from < to {
+                    // The other wall would write the same pair the other way around.
+                    writeln!(out, "portal {} {} {} {}", from.0, from.1, to.0, to.1).unwrap();
+                }
+            }
+        }
+
+        let player = &self.player().entity;
+        writeln!(out, "start {} {} {}", player.region, player.pos.x, player.pos.y).unwrap();
+        out
+    }
+}
+
+fn sorted_walls<'a>(walls: impl Iterator<Item = &'a Wall>) -> Vec<&'a Wall> {
+    let mut walls: Vec<&Wall> = walls.collect();
+    walls.sort_by_key(|wall| wall.id);
+    walls
+}
+
+/// Only writes out the parts that aren't the defaults.
+fn material_text(material: &Material) -> String {
+    let colour = material.colour;
+    let mut text = format!("colour {} {} {}", colour.r, colour.g, colour.b);
+    let default = Material::default(colour);
+    let properties = [
+        ("ambient", material.ambient, default.ambient),
+        ("diffuse", material.diffuse, default.diffuse),
+        ("specular", material.specular, default.specular),
+        ("shininess", material.shininess, default.shininess),
+    ];
+    for (name, value, default) in properties {
+        if value != default {
+            write!(text, " {} {}", name, value).unwrap();
+        }
+    }
+    text
+}
+
+/// A problem with a level file and the line it's on, counting from 1.
+#[derive(Debug, PartialEq)]
+pub(crate) struct LevelError {
+    pub(crate) line: usize,
+    pub(crate) message: String,
+}
+
+impl Display for LevelError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for LevelError {}
+
+/// The words on one line of a level file after its keyword.
+struct Tokens<'a> {
+    words: SplitWhitespace<'a>,
+    line: usize,
+}
+
+impl Tokens<'_> {
+    fn error(&self, message: &str) -> LevelError {
+        LevelError { line: self.line, message: message.to_string() }
+    }
+
+    fn number(&mut self) -> Result<f64, LevelError> {
+        let word = self.words.next().ok_or_else(|| self.error("Expected a number"))?;
+        word.parse().map_err(|_| self.error(&format!("Expected a number but found {}", word)))
+    }
+
+    fn index(&mut self) -> Result<usize, LevelError> {
+        let word = self.words.next().ok_or_else(|| self.error("Expected an index"))?;
+        word.parse().map_err(|_| self.error(&format!("Expected an index but found {}", word)))
+    }
+
+    fn vector(&mut self) -> Result<Vector2, LevelError> {
+        Ok(Vector2::of(self.number()?, self.number()?))
+    }
+
+    fn colour(&mut self) -> Result<Colour, LevelError> {
+        Ok(Colour::new(self.number()?, self.number()?, self.number()?))
+    }
+
+    fn keyword(&mut self, expected: &str) -> Result<(), LevelError> {
+        match self.words.next() {
+            Some(word) if word == expected => Ok(()),
+            Some(word) => Err(self.error(&format!("Expected {} but found {}", expected, word))),
+            None => Err(self.error(&format!("Expected {}", expected))),
+        }
+    }
+
+    /// Takes the rest of the line.
+    fn material(&mut self) -> Result<Material, LevelError> {
+        self.keyword("colour")?;
+        let mut material = Material::default(self.colour()?);
+        while let Some(name) = self.words.next() {
+            let value = self.number()?;
+            match name {
+                "ambient" => material.ambient = value,
+                "diffuse" => material.diffuse = value,
+                "specular" => material.specular = value,
+                "shininess" => material.shininess = value,
+                _ => return Err(self.error(&format!("Unknown material property {}", name))),
+            }
+        }
+        Ok(material)
+    }
+
+    fn current_region(&self, wall_counts: &[usize]) -> Result<usize, LevelError> {
+        wall_counts.len().checked_sub(1).ok_or_else(|| self.error("Expected a region first"))
+    }
+
+    fn end(&mut self) -> Result<(), LevelError> {
+        match self.words.next() {
+            None => Ok(()),
+            Some(word) => Err(self.error(&format!("Unexpected {}", word))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::event::MouseButton;
+    use crate::player::Player;
+    use crate::world_gen::{example_preset, random_maze_world, MazeSettings};
+
+    #[test]
+    fn round_trip() {
+        let mut settings = MazeSettings::new();
+        settings.seed = 1;
+        settings.wrap_east_west = true;
+        settings.mud = 0.2;
+        for world in [example_preset(), random_maze_world(&settings)] {
+            let text = world.to_level();
+            let loaded = load_level(&text).unwrap();
+            assert_eq!(loaded.to_level(), text);
+            assert_eq!(loaded.regions.len(), world.regions.len());
+        }
+    }
+
+    #[test]
+    fn player_portals() {
+        let mut world = random_maze_world(&MazeSettings::new());
+        Player::mouse_click(&mut world, MouseButton::Left);
+        world.player_mut().look_direction = world.player().look_direction.negate();
+        Player::mouse_click(&mut world, MouseButton::Right);
+        assert!(world.player().portals.iter().all(|portal| portal.is_some()));
+
+        let text = world.to_level();
+        assert_eq!(text.lines().filter(|line| line.starts_with("portal")).count(), 1);
+        assert_eq!(load_level(&text).unwrap().to_level(), text);
+
+        // Like the maze chain reusing the region one of them was in.
+        let gone = world.player().portals[1].unwrap();
+        world.regions[gone.region].remove_wall(gone.wall);
+        let text = world.to_level();
+        assert!(!text.contains("portal"));
+        assert_eq!(load_level(&text).unwrap().to_level(), text);
+    }
+
+    #[test]
+    fn region_bounds() {
+        let text = "region 0.5 -2 10.25 10 colour 1 1 1\n\nstart 0 5 5\n";
+        assert_eq!(load_level(text).unwrap().to_level(), text);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text: &str| load_level(text).err().unwrap();
+        assert_eq!(error("wall 0 0 1 1 normal 0 1 colour 1 1 1").line, 1);
+        assert_eq!(error("region 0 0 10 10 colour 1 1 1\n\nlight 1 one colour 1 1 1").line, 3);
+        assert_eq!(error("region 0 0 10 10 colour 1 1 1 shiny 3").message, "Unknown material property shiny");
+        assert_eq!(error("region 0 0 10 10 colour 1 1 1\nportal 0 0 0 1\nstart 0 5 5").message, "Region 0 has no wall 0");
+        assert_eq!(error("region 0 0 10 10 colour 1 1 1 # no start\n").line, 1);
+        assert_eq!(error("floor 0 0 10 10").message, "Unknown keyword floor");
+        assert!(load_level("# comments are fine\nregion 0 0 10 10 colour 1 1 1\nstart 0 5 5 # here\n").is_ok());
+    }
+}
@@ -1,5 +1,4 @@
 use std::cell::Cell;
-use std::collections::HashMap;
 use std::time::Instant;
 
 use crate::{mth::Vector2, world::World};
@@ -11,13 +10,14 @@ use crate::world::LightKind::PORTAL;
 impl World {
     pub(crate) fn init_lighting(&mut self){
         let portal_hits = self.collect_portal_lights();
-        for (_, portal_light) in portal_hits.into_iter() {
+        for mut portal_light in portal_hits.into_iter() {
+            portal_light.id = self.rng.rand();
             self.add_portal_light(portal_light);
         }
     }
 
-    fn collect_portal_lights(&self) -> HashMap<usize, LightSource> {
-        let mut portal_hits = HashMap::new();
+    fn collect_portal_lights(&self) -> Vec<LightSource> {
+        let mut portal_hits = vec![];
         for region in 0..self.regions.len() {
             let region = self.get_region(region);
             for light in region.lights(){
@@ -60,7 +60,8 @@ impl World {
 
 impl Region {
     /// Collect all times that a light hits a portal in its region.
-    fn trace_portal_light(&self, light: &LightSource, found: &mut HashMap<usize, LightSource>){
+    /// The new lights don't have ids yet because choosing them needs the world's random number generator.
+    fn trace_portal_light(&self, light: &LightSource, found: &mut Vec<LightSource>){
         assert_eq!(self.id, light.region);
         for wall in self.walls() {
             match wall.portal() {
@@ -87,7 +88,7 @@ impl Region {
                             let new_pos = portal.to_wall_line().middle().subtract(&offset);
 
                             let portal_light = LightSource {
-                                id: maze::rand(),
+                                id: 0,
                                 region: portal.to_region,
                                 intensity: light.intensity,
                                 pos: new_pos,
@@ -95,7 +96,7 @@ impl Region {
                                     portal_line: portal.to_wall_line()
                                 },
                             };
-                            found.insert(maze::rand(), portal_light);
+                            found.push(portal_light);
                             break;
                         }
                     }
@@ -105,10 +106,10 @@ impl Region {
     }
 
     pub(crate) fn new_light_cache(min: Vector2, max: Vector2) -> FloorLightCache {
-        let min = min.subtract(&Vector2::of(1.0, 1.0));
-        let max = max.add(&Vector2::of(1.0, 1.0));
-        let width = (max.x - min.x).abs().ceil() as usize;
-        let height = (max.y - min.y).abs().ceil() as usize;
+        let top_left = min.subtract(&Vector2::of(1.0, 1.0));
+        let bottom_right = max.add(&Vector2::of(1.0, 1.0));
+        let width = (bottom_right.x - top_left.x).abs().ceil() as usize;
+        let height = (bottom_right.y - top_left.y).abs().ceil() as usize;
 
         let cache = Region::empty_light_cache(width * height);
         FloorLightCache {
@@ -116,7 +117,9 @@ impl Region {
             height,
             floor_light_cache: cache.clone(),
             empty_floor_light_cache: cache,
-            top_left: min,
+            top_left,
+            min,
+            max,
         }
     }
 
@@ -18,9 +18,10 @@ impl Region {
 
     /// Calculates the colour of a point on the floor based on all lights in the region.
     pub(crate) fn horizontal_surface_colour(&self, hit_pos: Vector2) -> Colour {
-        let mut colour = self.floor_material.white_ambient();
+        let material = self.floor_material_at(hit_pos);
+        let mut colour = material.white_ambient();
         for light in self.lights() {
-            colour = colour.add(self.floor_lighting(&self.floor_material, light, hit_pos));
+            colour = colour.add(self.floor_lighting(material, light, hit_pos));
         }
         colour
     }
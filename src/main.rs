@@ -13,18 +13,21 @@ use winit::event_loop::EventLoop;
 use winit::window::{CursorGrabMode, WindowBuilder};
 use crate::camera::SoftBufferRender;
 use crate::material::Colour;
+use crate::world_gen::{MazeSettings, Scene};
 
 mod camera;
 mod camera2d;
 mod camera3d;
 mod entity;
 mod game;
+mod level;
 mod light_cache;
 mod lighting;
 mod material;
 mod mth;
 mod player;
 mod ray;
+mod wall_grid;
 mod world;
 mod world_gen;
 mod log;
@@ -60,7 +63,25 @@ fn main() {
     let window = builder.build(&event_loop).unwrap();
     window.set_cursor_grab(CursorGrabMode::Locked).unwrap();
 
-    let mut game = GameState::new();
+    #[cfg(not(target_arch = "wasm32"))]
+    let settings = match parse_args(std::env::args().skip(1)) {
+        Ok(settings) => settings,
+        Err(message) => {
+            eprintln!("{}", message);
+            eprintln!("Usage: mazerender2d [--scene <{}>] [--seed <number>] [--generator <{}>] [--shape <square|polar|hex|triangle|layered|weave|endless|chain>] [--size <cols>x<rows>] [--mask <file.txt|file.pbm>] [--wrap <east_west|north_south|both>] [--braid <0-1>] [--mud <0-1>] [--maze <file.txt>] [--level <file.txt>]", scene_names(), maze::gen::MazeGenerator::names().collect::<Vec<_>>().join("|"));
+            std::process::exit(1);
+        }
+    };
+    #[cfg(target_arch = "wasm32")]
+    let settings = match parse_args(query_args()) {
+        Ok(settings) => settings,
+        Err(message) => {
+            crate::println!("{}. Query parameters are the same as the native command line arguments, like ?scene=<{}>&seed=<number>", message, scene_names());
+            MazeSettings::new()
+        }
+    };
+
+    let mut game = GameState::new(settings);
     let context = unsafe { softbuffer::Context::new(&window) }.unwrap();
     let mut surface = unsafe { softbuffer::Surface::new(&context, &window) }.unwrap();
 
@@ -121,7 +142,10 @@ fn main() {
                         ..
                     } => match key {
                         VirtualKeyCode::Space => game.toggle_camera_mode(),
-                        VirtualKeyCode::R => game.reset_world(),
+                        VirtualKeyCode::R if state == ElementState::Pressed => game.reset_world(),
+                        VirtualKeyCode::G if state == ElementState::Pressed => game.carve_world(),
+                        VirtualKeyCode::N if state == ElementState::Pressed => game.next_scene(),
+                        VirtualKeyCode::L if state == ElementState::Pressed => game.save_level(),
                         VirtualKeyCode::W => game.keys.w = state == ElementState::Pressed,
                         VirtualKeyCode::A => game.keys.a = state == ElementState::Pressed,
                         VirtualKeyCode::S => game.keys.s = state == ElementState::Pressed,
@@ -149,3 +173,87 @@ fn main() {
         }
     });
 }
+
+/// Lets you regenerate a maze from a bug report by passing in the seed and algorithm it printed.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<MazeSettings, String> {
+    let mut settings = MazeSettings::new();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(format!("Missing value for {}", flag))?;
+        match flag.as_str() {
+            "--scene" => {
+                settings.scene = Scene::by_name(&value).ok_or(format!("Unknown scene {}", value))?;
+            }
+            "--seed" => {
+                settings.seed = value.parse().map_err(|_| format!("Invalid seed {}", value))?;
+            }
+            "--generator" => {
+                settings.generator = maze::gen::MazeGenerator::by_name(&value).ok_or(format!("Unknown generator {}", value))?;
+            }
+            "--shape" => {
+                settings.shape = crate::world_gen::Shape::by_name(&value).ok_or(format!("Unknown shape {}", value))?;
+            }
+            "--wrap" => {
+                let (east_west, north_south) = match value.as_str() {
+                    "east_west" => (true, false),
+                    "north_south" => (false, true),
+                    "both" => (true, true),
+                    _ => return Err(format!("Unknown wrap {}, expected east_west, north_south or both", value)),
+                };
+                settings.wrap_east_west = east_west;
+                settings.wrap_north_south = north_south;
+            }
+            "--braid" => {
+                settings.braid = value.parse().map_err(|_| format!("Invalid braid chance {}", value))?;
+            }
+            "--size" => {
+                let (cols, rows) = value.split_once('x').ok_or(format!("Invalid size {}, expected <cols>x<rows>", value))?;
+                let parse = |text: &str| text.parse().ok().filter(|n: &i32| *n > 0).ok_or(format!("Invalid size {}", value));
+                settings.size = (parse(cols)?, parse(rows)?);
+            }
+            "--mud" => {
+                settings.mud = value.parse().map_err(|_| format!("Invalid mud chance {}", value))?;
+            }
+            "--maze" => {
+                let text = std::fs::read_to_string(&value).map_err(|e| format!("Failed to read maze {}: {}", value, e))?;
+                settings.layout = Some(maze::Grid::from_ascii(&text).map_err(|e| format!("Invalid maze {}: {}", value, e))?);
+            }
+            "--level" => {
+                let text = std::fs::read_to_string(&value).map_err(|e| format!("Failed to read level {}: {}", value, e))?;
+                crate::level::load_level(&text).map_err(|e| format!("Invalid level {}: {}", value, e))?;
+                settings.level = Some(text);
+                settings.scene = Scene::Level;
+            }
+            "--mask" => {
+                settings.mask = Some(maze::Mask::from_file(std::path::Path::new(&value))?);
+            }
+            _ => return Err(format!("Unknown argument {}", flag)),
+        }
+    }
+    if settings.scene == Scene::Level && settings.level.is_none() {
+        return Err("The level scene needs a file from --level".to_string());
+    }
+    // A mask sets the size of the grid instead.
+    let (cols, rows) = settings.mask.as_ref().map_or(settings.size, |mask| (mask.cols, mask.rows));
+    if (settings.wrap_east_west && cols < 3) || (settings.wrap_north_south && rows < 3) {
+        return Err(format!("A {}x{} maze is too small to wrap, it needs at least 3 cells across each way it wraps", cols, rows));
+    }
+    Ok(settings)
+}
+
+/// Turns the page's query string into the same arguments as the command line, so ?scene=portals is --scene portals.
+#[cfg(target_arch = "wasm32")]
+fn query_args() -> impl Iterator<Item = String> {
+    let query = web_sys::window().unwrap().location().search().unwrap_or_default();
+    let pairs: Vec<String> = query.trim_start_matches('?').split('&')
+        .filter(|pair| !pair.is_empty())
+        .flat_map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            [format!("--{}", key), value.to_string()]
+        })
+        .collect();
+    pairs.into_iter()
+}
+
+fn scene_names() -> String {
+    Scene::ALL.iter().map(|(name, _)| *name).collect::<Vec<_>>().join("|")
+}
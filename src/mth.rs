@@ -184,7 +184,7 @@ impl LineSegment2 {
         } else if self.is_vertical() {
             Vector2::of(1.0, 0.0)
         } else {
-            let goal_slope = 1.0 / self.slope();
+            let goal_slope = -1.0 / self.slope();
             LineSegment2 {
                 a: self.a.clone(),
                 b: Vector2::of(self.a.x + 1.0, self.a.y + goal_slope),
@@ -378,6 +378,8 @@ mod tests {
         let line = LineSegment2::algebraic(2.0, 5.0);
         assert_eq!(line.slope(), 2.0);
         assert_eq!(line.y_intercept(), 5.0);
+        assert!(almost_equal(line.normal().dot(&line.direction()), 0.0));
+        assert!(almost_equal(line.normal().length(), 1.0));
 
         let a = LineSegment2::algebraic(3.0, 2.0);
         let b = LineSegment2::algebraic(2.0, 3.0);
@@ -9,7 +9,7 @@ use crate::mth;
 use crate::mth::{LineSegment2, Vector2};
 use crate::ray::RaySegment;
 use crate::world::{Portal, Wall, World};
-use maze::rand;
+use maze::Random;
 
 const MOVE_SPEED: f64 = 100.0;
 const TURN_SPEED: f64 = 0.002;
@@ -17,8 +17,8 @@ const PLAYER_SIZE: f64 = 4.0;
 
 #[derive(Copy, Clone)]
 pub(crate) struct WallRef {
-    region: usize,
-    wall: usize,
+    pub(crate) region: usize,
+    pub(crate) wall: usize,
 }
 
 pub(crate) struct Player {
@@ -32,11 +32,11 @@ pub(crate) struct Player {
 }
 
 impl Player {
-    pub(crate) fn new(start_region: usize, pos: Vector2) -> Player {
+    pub(crate) fn new(start_region: usize, pos: Vector2, rng: &mut Random) -> Player {
         Player {
             entity: SquareEntity {
                 id: 0,
-                bb_ids: [maze::rand(), maze::rand(), maze::rand(), maze::rand()],
+                bb_ids: [rng.rand(), rng.rand(), rng.rand(), rng.rand()],
                 pos,
                 region: start_region,
                 radius: 1.0,
@@ -66,6 +66,8 @@ impl Player {
             let dir = world.player_mut().move_direction;
             let move_direction = Player::handle_collisions(world, dir, 100.0);
 
+            Player::cross_seam(world, move_direction.scale(delta_time * MOVE_SPEED));
+
             let player = world.player_mut();
             player.entity.pos.x += move_direction.x * delta_time * MOVE_SPEED;
             player.entity.pos.y += move_direction.y * delta_time * MOVE_SPEED;
@@ -83,17 +85,11 @@ impl Player {
         mut move_direction: Vector2,
         max_dist_sq: f64,
     ) -> Vector2 {
-        let player = &mut world.player;
-        let region = &world.regions[player.entity.region];
-
-        let hit = region.single_ray_trace(player.entity.pos, move_direction);
-        if hit.line.length_sq() > max_dist_sq {
-            return move_direction;
-        }
-        match hit.hit_wall {
-            None => {}
-            Some(wall_index) => {
-                let wall = region.get_wall(wall_index);
+        match Player::nearest_wall(world, move_direction, max_dist_sq) {
+            None => return move_direction,
+            Some((region, wall_index)) => {
+                let player = &mut world.player;
+                let wall = world.regions[region].get_wall(wall_index);
                 let hit_back = wall.normal().dot(&move_direction) > 0.0;
                 let wall_dir_unit = wall.line().direction().normalize();
                 let slide_direction = wall_dir_unit.scale(move_direction.dot(&wall_dir_unit));
@@ -135,6 +131,50 @@ impl Player {
         }
     }
 
+    /// The region and id of the closest wall within range, carrying on over seamless portals into the next tile
+    /// so a wall just past a seam still blocks the way before cross_seam switches regions.
+    fn nearest_wall(world: &World, direction: Vector2, max_dist_sq: f64) -> Option<(usize, usize)> {
+        let start = world.player.entity.pos;
+        let mut origin = start;
+        let mut region = world.player.entity.region;
+        // Only going past a corner crosses more than one seam.
+        for _ in 0..4 {
+            let hit = world.regions[region].single_ray_trace(origin, direction);
+            if hit.line.b.subtract(&start).length_sq() > max_dist_sq {
+                return None;
+            }
+            let wall = world.regions[region].get_wall(hit.hit_wall?);
+            match wall.portal() {
+                Some(portal) if portal.is_seamless() && wall.normal().dot(&direction) < 0.0 => {
+                    region = portal.to_region;
+                    // Just over the seam so the same line in the next tile doesn't get hit straight away.
+                    origin = hit.line.b.add(&portal.transform.to_normal.scale(0.001));
+                }
+                _ => return Some((region, wall.id)),
+            }
+        }
+        None
+    }
+
+    /// Switches to the next region if the step goes over a seamless portal.
+    /// Nothing moves so it doesn't need to be translated like a normal portal.
+    fn cross_seam(world: &mut World, step: Vector2) {
+        let player = &mut world.player;
+        let region = &world.regions[player.entity.region];
+        let hit = region.single_ray_trace(player.entity.pos, step);
+        let Some(wall) = hit.hit_wall else {
+            return;
+        };
+        let wall = region.get_wall(wall);
+        let hit_front = wall.normal().dot(&step) < 0.0;
+        match wall.portal() {
+            Some(portal) if portal.is_seamless() && hit_front && hit.line.length_sq() <= step.length_sq() => {
+                player.entity.region = portal.to_region;
+            }
+            _ => {}
+        }
+    }
+
     fn update_direction(&mut self, pressed: &Keys, delta_mouse: i32) -> bool {
         let mut relative_move_direction = Vector2::zero();
         self.has_flash_light = false;
@@ -180,6 +220,7 @@ impl Player {
         match hit.hit_wall {
             None => {}
             Some(hit_wall_index) => {
+                let id = world.rng.rand();
                 let new_portal = {
                     let hit_wall = world.get_region(hit.region).get_wall(hit_wall_index);
                     let half_portal_direction = hit_wall.line.direction().normalize().scale(10.0);
@@ -201,7 +242,7 @@ impl Player {
                         .add(&normal.scale(bump_dist));
 
                     let wall = Wall {
-                        id: rand(),
+                        id,
                         region: hit.region,
                         line: LineSegment2::of(start_point, end_point),
                         normal,
@@ -239,7 +280,7 @@ impl Player {
         match portal {
             None => {}
             Some(portal) => {
-                world.regions[portal.region].walls.remove(&portal.wall);
+                world.regions[portal.region].remove_wall(portal.wall);
             }
         }
         world.player_mut().portals[portal_index] = None;
@@ -272,6 +313,6 @@ impl Player {
         }
 
         // Add the new portal to the world.
-        world.regions[portal.region].walls.insert(portal.id, portal);
+        world.regions[portal.region].insert_wall(portal);
     }
 }
@@ -36,7 +36,8 @@ impl World {
                         Some(portal)  => {
                             let t = hit_wall.line().t_of(&segment.line.b).abs();
                             let hit_back = hit_wall.normal().dot(&direction) > 0.0;
-                            let hit_edge = t < 0.01 || t > 0.99;
+                            // A seamless portal's edge is just where the next one starts, not the side of a wall.
+                            let hit_edge = !portal.is_seamless() && (t < 0.01 || t > 0.99);
                             if hit_back || hit_edge {
                                 break;
                             }
@@ -111,6 +112,7 @@ impl Region {
     }
 
     /// Sends a ray through a single region until it hits a wall. Without following portals.
+    /// Only checks the walls in the parts of the wall grid the ray goes through, stopping at the first part with a hit.
     pub(crate) fn single_ray_trace(&self, origin: Vector2, direction: Vector2) -> RaySegment {
         let ray = LineSegment2::from(origin, direction.scale(VIEW_DIST));
 
@@ -118,16 +120,20 @@ impl Region {
         let mut closest_hit_point = Vector2::NAN;
         let mut hit_wall = None;
 
-        for wall in self.walls() {
-            let hit = wall.line().intersection(&ray);
-            let to_hit = origin.subtract(&hit);
+        self.wall_grid().walk(&ray, |walls| {
+            for wall in walls {
+                let wall = self.get_wall(*wall);
+                let hit = wall.line().intersection(&ray);
+                let to_hit = origin.subtract(&hit);
 
-            if !hit.is_nan() && to_hit.length_sq() < shortest_hit_distance_squared {
-                hit_wall = Some(wall);
-                shortest_hit_distance_squared = to_hit.length_sq();
-                closest_hit_point = hit;
+                if !hit.is_nan() && to_hit.length_sq() < shortest_hit_distance_squared {
+                    hit_wall = Some(wall);
+                    shortest_hit_distance_squared = to_hit.length_sq();
+                    closest_hit_point = hit;
+                }
             }
-        }
+            shortest_hit_distance_squared
+        });
 
         let hit_result = match hit_wall {
             None => {
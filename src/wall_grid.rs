@@ -0,0 +1,222 @@
+use crate::mth::{LineSegment2, Vector2};
+use crate::world::Wall;
+
+/// How big each square of the grid is, the same as a maze cell so most squares only have a few walls.
+const SQUARE_SIZE: f64 = 50.0;
+/// Regions spread out further than this many squares use bigger ones so the grid doesn't get huge.
+const MAX_SQUARES_ACROSS: f64 = 256.0;
+/// Walls go in every square they come this close to, so a hit right on the edge of a square is found from either side.
+const MARGIN: f64 = 0.01;
+
+/// Buckets the walls of a region by which squares of a grid they pass through,
+/// so a ray only has to check the walls in the squares it goes through instead of every wall in the region.
+pub(crate) struct WallGrid {
+    top_left: Vector2,
+    square_size: f64,
+    cols: i32,
+    rows: i32,
+    /// The ids of the walls in each square, row by row.
+    squares: Vec<Vec<usize>>,
+}
+
+impl WallGrid {
+    /// A grid just big enough for the walls.
+    pub(crate) fn new<'a>(walls: impl Iterator<Item = &'a Wall> + Clone) -> WallGrid {
+        let mut min = Vector2::of(f64::INFINITY, f64::INFINITY);
+        let mut max = Vector2::of(f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for wall in walls.clone() {
+            for point in [wall.line.a, wall.line.b] {
+                min = Vector2::of(min.x.min(point.x), min.y.min(point.y));
+                max = Vector2::of(max.x.max(point.x), max.y.max(point.y));
+            }
+        }
+        if min.x > max.x {
+            return WallGrid::empty();
+        }
+
+        let top_left = min.subtract(&Vector2::of(MARGIN, MARGIN));
+        let size = max.subtract(&min).add(&Vector2::of(MARGIN * 2.0, MARGIN * 2.0));
+        let square_size = SQUARE_SIZE.max(size.x.max(size.y) / MAX_SQUARES_ACROSS);
+        let cols = (size.x / square_size).floor() as i32 + 1;
+        let rows = (size.y / square_size).floor() as i32 + 1;
+        let mut grid = WallGrid {
+            top_left,
+            square_size,
+            cols,
+            rows,
+            squares: vec![vec![]; (cols * rows) as usize],
+        };
+        for wall in walls {
+            assert!(grid.insert(wall));
+        }
+        grid
+    }
+
+    pub(crate) fn empty() -> WallGrid {
+        WallGrid {
+            top_left: Vector2::zero(),
+            square_size: SQUARE_SIZE,
+            cols: 0,
+            rows: 0,
+            squares: vec![],
+        }
+    }
+
+    /// Returns false without adding it if the wall doesn't fit in the grid, so it has to be made again bigger.
+    pub(crate) fn insert(&mut self, wall: &Wall) -> bool {
+        let Some(squares) = self.squares_near(&wall.line) else {
+            return false;
+        };
+        for square in squares {
+            self.squares[square].push(wall.id);
+        }
+        true
+    }
+
+    pub(crate) fn remove(&mut self, wall: &Wall) {
+        for square in self.squares_near(&wall.line).unwrap_or_default() {
+            self.squares[square].retain(|id| *id != wall.id);
+        }
+    }
+
+    /// Every square the line's bounding box comes within the margin of, or None if any are off the grid.
+    fn squares_near(&self, line: &LineSegment2) -> Option<Vec<usize>> {
+        let square = |x: f64, y: f64| {
+            let local = Vector2::of(x, y).subtract(&self.top_left).scale(1.0 / self.square_size);
+            (local.y.floor() as i32, local.x.floor() as i32)
+        };
+        let (first_row, first_col) = square(line.a.x.min(line.b.x) - MARGIN, line.a.y.min(line.b.y) - MARGIN);
+        let (last_row, last_col) = square(line.a.x.max(line.b.x) + MARGIN, line.a.y.max(line.b.y) + MARGIN);
+        if first_row < 0 || first_col < 0 || last_row >= self.rows || last_col >= self.cols {
+            return None;
+        }
+
+        let mut squares = vec![];
+        for row in first_row..=last_row {
+            for col in first_col..=last_col {
+                squares.push((row * self.cols + col) as usize);
+            }
+        }
+        Some(squares)
+    }
+
+    /// Walks along the ray one square at a time, in order, passing the walls in each one to check.
+    /// The check returns the squared distance to the closest hit so far, so it can stop once there's a hit before the next square.
+    pub(crate) fn walk(&self, ray: &LineSegment2, mut check: impl FnMut(&[usize]) -> f64) {
+        // Everything is in units of squares from the top left of the grid, and t goes from 0 at the start of the ray to 1 at the end.
+        let start = ray.a.subtract(&self.top_left).scale(1.0 / self.square_size);
+        let delta = ray.b.subtract(&ray.a).scale(1.0 / self.square_size);
+        let length_sq = ray.length_sq();
+
+        // Only the part of the ray inside the grid.
+        let (mut t_start, mut t_end) = (0.0_f64, 1.0_f64);
+        for (start, delta, count) in [(start.x, delta.x, self.cols), (start.y, delta.y, self.rows)] {
+            if delta == 0.0 {
+                if start < 0.0 || start >= count as f64 {
+                    return;
+                }
+            } else {
+                let (enter, exit) = (-start / delta, (count as f64 - start) / delta);
+                t_start = t_start.max(enter.min(exit));
+                t_end = t_end.min(enter.max(exit));
+            }
+        }
+        if t_start > t_end {
+            return;
+        }
+
+        let axis = |start: f64, delta: f64, count: i32| {
+            let square = ((start + delta * t_start).floor() as i32).clamp(0, count - 1);
+            let step = if delta > 0.0 { 1 } else { -1 };
+            // When the ray crosses into the next square along this axis and how long it takes to cross a whole one.
+            let next = if delta > 0.0 {
+                (square as f64 + 1.0 - start) / delta
+            } else if delta < 0.0 {
+                (square as f64 - start) / delta
+            } else {
+                f64::INFINITY
+            };
+            (square, step, next, (1.0 / delta).abs())
+        };
+        let (mut col, step_col, mut next_col, t_per_col) = axis(start.x, delta.x, self.cols);
+        let (mut row, step_row, mut next_row, t_per_row) = axis(start.y, delta.y, self.rows);
+
+        loop {
+            let closest_sq = check(&self.squares[(row * self.cols + col) as usize]);
+            let t_leave = next_col.min(next_row);
+            if t_leave >= t_end || closest_sq <= t_leave * t_leave * length_sq {
+                return;
+            }
+
+            if next_col < next_row {
+                col += step_col;
+                next_col += t_per_col;
+            } else {
+                row += step_row;
+                next_row += t_per_row;
+            }
+            if col < 0 || col >= self.cols || row < 0 || row >= self.rows {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maze::Random;
+    use crate::material::Material;
+    use crate::mth::{LineSegment2, Vector2};
+    use crate::ray::VIEW_DIST;
+    use crate::world::{Region, Wall, World};
+    use crate::world_gen::{example_preset, random_maze_world, MazeSettings, Shape};
+
+    /// The closest hit found by checking every wall.
+    fn every_wall(region: &Region, origin: Vector2, direction: Vector2) -> Option<Vector2> {
+        let ray = LineSegment2::from(origin, direction.scale(VIEW_DIST));
+        region.walls()
+            .map(|wall| wall.line().intersection(&ray))
+            .filter(|hit| !hit.is_nan())
+            .min_by(|a, b| a.subtract(&origin).length_sq().total_cmp(&b.subtract(&origin).length_sq()))
+    }
+
+    fn fraction(rng: &mut Random) -> f64 {
+        rng.rand_below(10000) as f64 / 10000.0
+    }
+
+    fn assert_same_hits(world: &World, rng: &mut Random) {
+        for region in world.regions() {
+            let area = region.lighting.top_left;
+            let size = Vector2::of(region.lighting.width as f64, region.lighting.height as f64);
+            for _ in 0..500 {
+                let origin = Vector2::of(area.x + size.x * fraction(rng), area.y + size.y * fraction(rng));
+                let direction = Vector2::from_angle(fraction(rng) * std::f64::consts::TAU, 1.0);
+                let expected = every_wall(region, origin, direction);
+                let found = region.single_ray_trace(origin, direction);
+                match expected {
+                    None => assert!(found.hit_wall.is_none()),
+                    Some(hit) => assert!(found.line.b.subtract(&hit).length() < 1e-6, "{:?} towards {:?}", origin, direction),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_as_every_wall() {
+        let mut rng = Random::new(3);
+        let mut settings = MazeSettings::new();
+        settings.seed = 3;
+        settings.shape = Shape::Polar;
+        assert_same_hits(&random_maze_world(&settings), &mut rng);
+        settings.shape = Shape::Square;
+        settings.size = (30, 20);
+        assert_same_hits(&random_maze_world(&settings), &mut rng);
+
+        let mut world = example_preset();
+        assert_same_hits(&world, &mut rng);
+        let far_away = LineSegment2::of(Vector2::of(900.0, 100.0), Vector2::of(900.0, 600.0));
+        world.add_wall(Wall { id: 100, region: 1, line: far_away, normal: far_away.normal(), material: Material::new(1.0, 1.0, 1.0), portal: None });
+        world.remove_wall(0, 0);
+        assert_same_hits(&world, &mut rng);
+    }
+}
@@ -2,6 +2,8 @@ use crate::game::Keys;
 use crate::material::{Colour, Material};
 use crate::mth::{LineSegment2, Vector2};
 use crate::player::Player;
+use crate::wall_grid::WallGrid;
+use maze::Random;
 use std::cell::Cell;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
@@ -9,22 +11,39 @@ use std::sync::{Arc, RwLock};
 pub(crate) struct World {
     pub(crate) regions: Vec<Region>,
     pub(crate) player: Player,
+    pub(crate) rng: Random,
 }
 
 pub(crate) struct Region {
     pub(crate) id: usize,
-    pub(crate) walls: HashMap<usize, Wall>,
+    /// Only changed through insert_wall and remove_wall so the wall grid stays up to date.
+    walls: HashMap<usize, Wall>,
+    wall_grid: WallGrid,
+    /// The id new_wall gives the next wall, so ids aren't reused after walls are removed.
+    next_wall_id: usize,
     pub(crate) lights: HashMap<usize, LightSource>,
     pub(crate) floor_material: Material,
+    /// Parts of the floor that use a different material instead, checked in order.
+    pub(crate) floor_patches: Vec<FloorPatch>,
     pub(crate) lighting: FloorLightCache,
 }
 
+/// A rectangle of floor with its own material, like the mud in a weighted maze cell.
+pub(crate) struct FloorPatch {
+    pub(crate) min: Vector2,
+    pub(crate) max: Vector2,
+    pub(crate) material: Material,
+}
+
 pub(crate) struct FloorLightCache {
     pub(crate) floor_light_cache: Box<[Cell<Option<Colour>>]>,
     pub(crate) empty_floor_light_cache: Box<[Cell<Option<Colour>>]>,
     pub(crate) width: usize,
     pub(crate) height: usize,
     pub(crate) top_left: Vector2,
+    /// The corners of the region it was made for, since the cache is bigger and rounded up to whole units.
+    pub(crate) min: Vector2,
+    pub(crate) max: Vector2,
 }
 
 #[derive(Clone, Copy)]
@@ -83,10 +102,12 @@ impl World {
         regions: Vec<Region>,
         start_region_index: usize,
         start_pos: Vector2,
+        mut rng: Random,
     ) -> World {
         let mut world = World {
             regions,
-            player: Player::new(start_region_index, start_pos),
+            player: Player::new(start_region_index, start_pos, &mut rng),
+            rng,
         };
 
         // let bb = world.player.entity.get_bounding_box();
@@ -118,25 +139,88 @@ impl World {
     }
 
     pub(crate) fn wall_mut(&mut self, region: usize, wall: usize) -> &mut Wall {
-        self.regions[region]
-            .walls
-            .get_mut(&wall)
-            .expect("Invalid wall index.")
+        self.regions[region].wall_mut(wall)
     }
 
     pub(crate) fn remove_wall(&mut self, region: usize, wall: usize) {
         self.regions[region]
-            .walls
-            .remove(&wall)
+            .remove_wall(wall)
             .expect("Invalid wall index");
     }
 
     pub(crate) fn add_wall(&mut self, wall: Wall) {
-        self.regions[wall.region].walls.insert(wall.id, wall);
+        self.regions[wall.region].insert_wall(wall);
     }
 }
 
 impl Region {
+    pub(crate) fn new(id: usize, floor_material: Material, lighting: FloorLightCache) -> Region {
+        Region {
+            id,
+            walls: HashMap::new(),
+            wall_grid: WallGrid::empty(),
+            next_wall_id: 0,
+            lights: HashMap::new(),
+            floor_material,
+            floor_patches: vec![],
+            lighting,
+        }
+    }
+
+    /// Is the pos inside the area the region was made to cover.
+    pub(crate) fn contains(&self, pos: Vector2) -> bool {
+        let (min, max) = (self.lighting.min, self.lighting.max);
+        pos.x >= min.x && pos.x <= max.x && pos.y >= min.y && pos.y <= max.y
+    }
+
+    pub(crate) fn wall_grid(&self) -> &WallGrid {
+        &self.wall_grid
+    }
+
+    /// Replaces any wall with the same id.
+    pub(crate) fn insert_wall(&mut self, wall: Wall) {
+        self.remove_wall(wall.id);
+        if self.wall_grid.insert(&wall) {
+            self.walls.insert(wall.id, wall);
+        } else {
+            // It's outside the grid so make a bigger one.
+            self.walls.insert(wall.id, wall);
+            self.wall_grid = WallGrid::new(self.walls.values());
+        }
+    }
+
+    /// Adds a wall with an id no other wall made this way has had since the walls were cleared.
+    pub(crate) fn new_wall(&mut self, line: LineSegment2, normal: Vector2, material: Material) -> usize {
+        let id = self.next_wall_id;
+        self.next_wall_id += 1;
+        self.insert_wall(Wall {
+            id,
+            region: self.id,
+            line,
+            normal,
+            material,
+            portal: None,
+        });
+        id
+    }
+
+    pub(crate) fn remove_wall(&mut self, id: usize) -> Option<Wall> {
+        let wall = self.walls.remove(&id)?;
+        self.wall_grid.remove(&wall);
+        Some(wall)
+    }
+
+    pub(crate) fn clear_walls(&mut self) {
+        self.walls.clear();
+        self.wall_grid = WallGrid::empty();
+        self.next_wall_id = 0;
+    }
+
+    /// Only for changing where a portal goes. Moving the wall would leave it in the wrong part of the wall grid.
+    pub(crate) fn wall_mut(&mut self, id: usize) -> &mut Wall {
+        self.walls.get_mut(&id).expect("Invalid wall index.")
+    }
+
     pub(crate) fn get_wall(&self, id: usize) -> &Wall {
         self.walls.get(&id).expect("Invalid wall id.")
     }
@@ -145,6 +229,13 @@ impl Region {
         self.walls.values()
     }
 
+    pub(crate) fn floor_material_at(&self, pos: Vector2) -> &Material {
+        let patch = self.floor_patches.iter().find(|patch| {
+            pos.x >= patch.min.x && pos.x < patch.max.x && pos.y >= patch.min.y && pos.y < patch.max.y
+        });
+        patch.map_or(&self.floor_material, |patch| &patch.material)
+    }
+
     pub(crate) fn lights(&self) -> impl Iterator<Item = &LightSource> {
         self.lights.values()
     }
@@ -174,6 +265,12 @@ impl Portal {
         })
     }
 
+    /// Joins two walls in the same place, so anything going through carries on as if the portal wasn't there.
+    pub(crate) fn is_seamless(&self) -> bool {
+        let (from, to) = (self.transform.from_line, self.transform.to_line);
+        from.a == to.a && from.b == to.b
+    }
+
     pub(crate) fn scale_factor(&self) -> f64 {
         // Calculate ratio of lengths with only one square root makes me feel very clever.
         (self.transform.to_line.length_sq() / self.transform.from_line.length_sq()).sqrt()